@@ -0,0 +1,16 @@
+use std::env;
+use std::path::Path;
+
+fn main() {
+    let out = env::var("OUT_DIR").expect("Out dir not found");
+    let mut registry = shader::ShaderRegistry::new();
+    registry.add_roots_from("src/shaders/roots").unwrap_or_else(|error| panic!("{}", error));
+    println!("cargo:rerun-if-changed=src/shaders/roots");
+
+    // Uniform structs come from the shaders themselves so the two can't drift apart
+    shader::Bindings::new()
+        .registry(registry)
+        .shader("src/shaders/default.wgsl")
+        .shader("src/shaders/post.wgsl")
+    .write(Path::new(&out).join("shaders.rs")).unwrap_or_else(|error| panic!("{}", error));
+}
@@ -0,0 +1,373 @@
+use quote::quote;
+use proc_macro2::TokenStream;
+use syn::punctuated::Punctuated;
+use syn::parse::{Parse, ParseStream};
+use syn::{Data, DeriveInput, Error, Expr, Field, Fields, GenericArgument};
+use syn::{Ident, Lit, LitInt, LitStr, Meta, PathArguments, Token, Type};
+
+const KINDS: &[&str] = &["uniform", "storage", "texture", "sampler"];
+
+enum Kind {
+    Uniform { dynamic: bool },
+    Storage { read_only: bool },
+    Texture { dimension: TokenStream, sample_type: TokenStream, multisampled: bool },
+    Sampler { ty: TokenStream },
+}
+
+struct Binding {
+    index: u32,
+    kind: Kind,
+    ty: Type,
+    field: Ident,
+    label: String,
+    visibility: TokenStream,
+}
+
+/// `(0, dynamic, visibility(vertex))`, the binding comes first and can be left out
+struct Args {
+    binding: Option<LitInt>,
+    options: Vec<Meta>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let binding = match input.peek(LitInt) {
+            true => Some(input.parse::<LitInt>()?),
+            false => None,
+        };
+        if binding.is_some() && !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+
+        let options = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+        return Ok(Self {
+            binding: binding,
+            options: options.into_iter().collect(),
+        });
+    }
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "BindGroup can't be derived for generic structs"));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(name, "BindGroup can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(name, "BindGroup needs a struct with named fields"));
+    };
+
+    let mut label = name.to_string();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("bind_group")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("label") {
+                label = meta.value()?.parse::<LitStr>()?.value();
+                return Ok(());
+            }
+            return Err(meta.error("expected `label = \"...\"`"));
+        })?;
+    }
+
+    // Bindings without a number follow the one before them
+    let mut next = 0;
+    let mut unbound = false;
+    let mut bindings = Vec::new();
+    for field in &fields.named {
+        let mut binding = None;
+        for attr in &field.attrs {
+            let Some(kind) = KINDS.iter().find(|kind| attr.path().is_ident(kind)) else {
+                continue;
+            };
+            if binding.is_some() {
+                return Err(Error::new_spanned(attr, "a field can only be bound once"));
+            }
+            binding = Some(parse_binding(field, attr, kind, next)?);
+        }
+
+        match binding {
+            Some(binding) => {
+                next = binding.index + 1;
+                bindings.push(binding);
+            }
+            None => unbound = true,
+        }
+    }
+
+    bindings.sort_by_key(|binding| binding.index);
+    for pair in bindings.windows(2) {
+        if pair[0].index == pair[1].index {
+            return Err(Error::new_spanned(&pair[1].field, format!("binding {} is used more than once", pair[1].index)));
+        }
+    }
+
+    let entries = bindings.iter().map(layout_entry);
+    let resources = bindings.iter().map(|binding| {
+        let index = binding.index;
+        let field = &binding.field;
+        let resource = match binding.kind {
+            Kind::Uniform { dynamic: true } => quote!(self.#field.as_binding()),
+            Kind::Uniform { dynamic: false } | Kind::Storage { .. } => quote!(self.#field.as_entire_binding()),
+            Kind::Texture { .. } => quote!(::gfx_ne::wgpu::BindingResource::TextureView(&self.#field.view)),
+            Kind::Sampler { .. } => quote!(::gfx_ne::wgpu::BindingResource::Sampler(&self.#field.sampler)),
+        };
+        return quote! {
+            ::gfx_ne::wgpu::BindGroupEntry {
+                binding: #index,
+                resource: #resource,
+            }
+        };
+    });
+
+    let uniforms: Vec<_> = bindings.iter().filter(|binding| matches!(binding.kind, Kind::Uniform { .. })).collect();
+    let uploads = uniforms.iter().map(|binding| &binding.field);
+    // Only uniforms know how to make themselves, anything else has to be passed in
+    let init = match unbound || uniforms.len() != bindings.len() {
+        true => quote!(panic!("{} requires a pre init state!", #label)),
+        false => {
+            let fields = uniforms.iter().map(|binding| {
+                let ty = &binding.ty;
+                let field = &binding.field;
+                let label = &binding.label;
+                return match binding.kind {
+                    Kind::Uniform { dynamic: true } => quote!(#field: <#ty>::new(device, Some(#label))),
+                    _ => quote!(#field: <#ty>::new(device, Some(#label), ::std::default::Default::default())),
+                };
+            });
+            quote!(return Self { #(#fields,)* };)
+        }
+    };
+
+    let dynamic: Vec<_> = bindings.iter().filter(|binding| matches!(binding.kind, Kind::Uniform { dynamic: true })).map(|binding| &binding.field).collect();
+    let dynamic_offsets = match dynamic.is_empty() {
+        true => quote!(),
+        false => quote! {
+            fn dynamic_offsets(&self, slot: usize) -> ::std::vec::Vec<u32> {
+                return ::std::vec![#(self.#dynamic.offset(slot)),*];
+            }
+        },
+    };
+
+    // Arenas and storage buffers get reallocated when they grow
+    let growable: Vec<_> = bindings.iter().filter(|binding| match binding.kind {
+        Kind::Uniform { dynamic } => dynamic,
+        Kind::Storage { .. } => true,
+        _ => false,
+    }).map(|binding| &binding.field).collect();
+    let generation = match growable.is_empty() {
+        true => quote!(),
+        false => quote! {
+            fn generation(&self) -> u64 {
+                return 0 #(+ self.#growable.generation())*;
+            }
+        },
+    };
+
+    let layout_label = format!("{} Layout", label);
+    return Ok(quote! {
+        impl ::gfx_ne::BindGroupState for #name {
+            fn get_name(&self) -> &'static str {
+                return #label;
+            }
+
+            #[allow(unused_variables)]
+            fn init(device: &::gfx_ne::wgpu::Device) -> Self {
+                #init
+            }
+
+            #[allow(unused_variables)]
+            fn update(&mut self, uploads: &mut ::gfx_ne::StagingBelt) {
+                #(self.#uploads.upload(uploads);)*
+            }
+
+            fn get_layout_descriptor(&self) -> ::gfx_ne::wgpu::BindGroupLayoutDescriptor<'_> {
+                // Borrow checker doesnt like when this isnt a static
+                static ENTRIES: &[::gfx_ne::wgpu::BindGroupLayoutEntry] = &[#(#entries),*];
+                return ::gfx_ne::wgpu::BindGroupLayoutDescriptor {
+                    label: Some(#layout_label),
+                    entries: ENTRIES,
+                };
+            }
+
+            fn create_bind_group(&self, device: &::gfx_ne::wgpu::Device, layout: &::gfx_ne::wgpu::BindGroupLayout) -> ::gfx_ne::wgpu::BindGroup {
+                return device.create_bind_group(&::gfx_ne::wgpu::BindGroupDescriptor {
+                    label: Some(#label),
+                    layout: layout,
+                    entries: &[#(#resources),*],
+                });
+            }
+
+            #dynamic_offsets
+            #generation
+        }
+    });
+}
+
+fn parse_binding(field: &Field, attr: &syn::Attribute, kind: &str, next: u32) -> syn::Result<Binding> {
+    let args = match &attr.meta {
+        Meta::Path(_) => Args { binding: None, options: Vec::new() },
+        _ => attr.parse_args::<Args>()?,
+    };
+
+    let mut label = None;
+    let mut dynamic = false;
+    let mut read_only = false;
+    let mut multisampled = false;
+    let mut visibility = None;
+    let mut dimension = quote!(D2);
+    let mut sample_type = quote!(Float { filterable: true });
+    let mut sampler = quote!(Filtering);
+    for option in &args.options {
+        let path = option.path();
+        let Some(ident) = path.get_ident().map(Ident::to_string) else {
+            return Err(Error::new_spanned(path, "expected a binding option"));
+        };
+
+        match (kind, ident.as_str()) {
+            (_, "label") => label = Some(string(option)?.value()),
+            (_, "visibility") => visibility = Some(stages(option)?),
+            ("uniform", "dynamic") => dynamic = true,
+            ("storage", "read_only") => read_only = true,
+            ("texture", "multisampled") => multisampled = true,
+            ("texture", "dim") => {
+                let value = string(option)?;
+                dimension = match value.value().as_str() {
+                    "1d" => quote!(D1),
+                    "2d" => quote!(D2),
+                    "2d_array" => quote!(D2Array),
+                    "cube" => quote!(Cube),
+                    "cube_array" => quote!(CubeArray),
+                    "3d" => quote!(D3),
+                    _ => return Err(Error::new_spanned(value, "expected 1d, 2d, 2d_array, cube, cube_array or 3d")),
+                };
+            }
+            ("texture", "sample_type") => {
+                let value = string(option)?;
+                sample_type = match value.value().as_str() {
+                    "float" => quote!(Float { filterable: true }),
+                    "unfilterable_float" => quote!(Float { filterable: false }),
+                    "depth" => quote!(Depth),
+                    "sint" => quote!(Sint),
+                    "uint" => quote!(Uint),
+                    _ => return Err(Error::new_spanned(value, "expected float, unfilterable_float, depth, sint or uint")),
+                };
+            }
+            ("sampler", "filtering") => sampler = quote!(Filtering),
+            ("sampler", "non_filtering") => sampler = quote!(NonFiltering),
+            ("sampler", "comparison") => sampler = quote!(Comparison),
+            _ => return Err(Error::new_spanned(path, format!("unknown option `{}` for a {} binding", ident, kind))),
+        }
+    }
+
+    let field_name = field.ident.clone().unwrap();
+    return Ok(Binding {
+        ty: field.ty.clone(),
+        label: label.unwrap_or_else(|| field_name.to_string()),
+        field: field_name,
+        // Vertex shaders can't write to storage buffers, wgpu rejects the layout if they could
+        visibility: visibility.unwrap_or_else(|| match (kind, read_only) {
+            ("storage", false) => quote!(::gfx_ne::wgpu::ShaderStages::FRAGMENT.union(::gfx_ne::wgpu::ShaderStages::COMPUTE)),
+            _ => quote!(::gfx_ne::wgpu::ShaderStages::VERTEX_FRAGMENT),
+        }),
+        index: match args.binding {
+            Some(binding) => binding.base10_parse()?,
+            None => next,
+        },
+        kind: match kind {
+            "uniform" => Kind::Uniform { dynamic: dynamic },
+            "storage" => Kind::Storage { read_only: read_only },
+            "texture" => Kind::Texture { dimension: dimension, sample_type: sample_type, multisampled: multisampled },
+            _ => Kind::Sampler { ty: sampler },
+        },
+    });
+}
+
+fn layout_entry(binding: &Binding) -> TokenStream {
+    let index = binding.index;
+    let visibility = &binding.visibility;
+    let ty = match &binding.kind {
+        Kind::Uniform { dynamic } => {
+            let size = match element(&binding.ty) {
+                Some(element) => quote!(::std::num::NonZeroU64::new(::std::mem::size_of::<#element>() as u64)),
+                None => quote!(None),
+            };
+            quote! {
+                ::gfx_ne::wgpu::BindingType::Buffer {
+                    has_dynamic_offset: #dynamic,
+                    min_binding_size: #size,
+                    ty: ::gfx_ne::wgpu::BufferBindingType::Uniform,
+                }
+            }
+        }
+        Kind::Storage { read_only } => quote! {
+            ::gfx_ne::wgpu::BindingType::Buffer {
+                min_binding_size: None,
+                has_dynamic_offset: false,
+                ty: ::gfx_ne::wgpu::BufferBindingType::Storage { read_only: #read_only },
+            }
+        },
+        Kind::Texture { dimension, sample_type, multisampled } => quote! {
+            ::gfx_ne::wgpu::BindingType::Texture {
+                multisampled: #multisampled,
+                sample_type: ::gfx_ne::wgpu::TextureSampleType::#sample_type,
+                view_dimension: ::gfx_ne::wgpu::TextureViewDimension::#dimension,
+            }
+        },
+        Kind::Sampler { ty } => quote!(::gfx_ne::wgpu::BindingType::Sampler(::gfx_ne::wgpu::SamplerBindingType::#ty)),
+    };
+
+    return quote! {
+        ::gfx_ne::wgpu::BindGroupLayoutEntry {
+            binding: #index,
+            visibility: #visibility,
+            ty: #ty,
+            count: None,
+        }
+    };
+}
+
+/// The `T` in `UniformBuffer<T>` or `UniformArena<T>`, which sets the minimum binding size
+fn element(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let PathArguments::AngleBracketed(arguments) = &path.path.segments.last()?.arguments else {
+        return None;
+    };
+    return arguments.args.iter().find_map(|argument| match argument {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+}
+
+fn string(option: &Meta) -> syn::Result<LitStr> {
+    if let Meta::NameValue(value) = option {
+        if let Expr::Lit(literal) = &value.value {
+            if let Lit::Str(string) = &literal.lit {
+                return Ok(string.clone());
+            }
+        }
+    }
+    return Err(Error::new_spanned(option, "expected a string, like `name = \"...\"`"));
+}
+
+/// `visibility(vertex, fragment)` as a `ShaderStages` constant
+fn stages(option: &Meta) -> syn::Result<TokenStream> {
+    let Meta::List(list) = option else {
+        return Err(Error::new_spanned(option, "expected `visibility(vertex, fragment, compute)`"));
+    };
+
+    let mut stages = quote!(::gfx_ne::wgpu::ShaderStages::NONE);
+    list.parse_nested_meta(|meta| {
+        let stage = match meta.path.get_ident().map(Ident::to_string).as_deref() {
+            Some("vertex") => quote!(VERTEX),
+            Some("fragment") => quote!(FRAGMENT),
+            Some("compute") => quote!(COMPUTE),
+            _ => return Err(meta.error("expected vertex, fragment or compute")),
+        };
+        stages = quote!(#stages.union(::gfx_ne::wgpu::ShaderStages::#stage));
+        return Ok(());
+    })?;
+    return Ok(stages);
+}
@@ -0,0 +1,141 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, LitStr};
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "GpuLayout can't be derived for generic structs"));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(name, "GpuLayout can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(name, "GpuLayout needs a struct with named fields"));
+    };
+    if fields.named.is_empty() {
+        return Err(Error::new_spanned(name, "GpuLayout needs at least one field"));
+    }
+
+    let mut wgsl = name.to_string();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("gpu_layout")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("wgsl") {
+                wgsl = meta.value()?.parse::<LitStr>()?.value();
+                return Ok(());
+            }
+            return Err(meta.error("expected `wgsl = \"...\"`"));
+        })?;
+    }
+
+    let count = fields.named.len();
+    let types = fields.named.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let idents = fields.named.iter().map(|field| field.ident.as_ref().unwrap()).collect::<Vec<_>>();
+    let members = idents.iter().map(|ident| ident.to_string()).collect::<Vec<_>>();
+    let paddings = (0..=count).map(|i| format_ident!("__padding{}", i)).collect::<Vec<_>>();
+    let (tail, paddings) = paddings.split_last().unwrap();
+    let tail_index = count;
+    let indices = 0..count;
+
+    // The same struct is generated for each layout, only the member sizes and alignments change
+    let mut items = Vec::new();
+    for (layout, space) in [("Std140", "var<uniform>"), ("Std430", "var<storage>")] {
+        let repr = format_ident!("{}", layout);
+        let target = format_ident!("{}{}", name, layout);
+        let align = format_ident!("{}_ALIGN", layout.to_uppercase());
+        let constant = format_ident!("__{}_{}", name.to_string().to_uppercase(), layout.to_uppercase());
+        let vis_fields = fields.named.iter().map(|field| &field.vis);
+        let indices = indices.clone();
+        let message = format!("`{}` doesn't match its {} layout", target, layout.to_lowercase());
+        let doc = format!("[`{}`] laid out for `{}`", name, space);
+
+        items.push(quote! {
+            #[doc(hidden)]
+            const #constant: ::gfx_ne::StructLayout<#count> = ::gfx_ne::StructLayout::new([
+                #((<#types as ::gfx_ne::GpuLayout>::#align, ::std::mem::size_of::<<#types as ::gfx_ne::GpuLayout>::#repr>())),*
+            ], ::gfx_ne::MemoryLayout::#repr);
+
+            #[doc = #doc]
+            #[repr(C)]
+            #[derive(Debug, Clone, Copy)]
+            #vis struct #target {
+                #(#paddings: [u8; #constant.padding(#indices)], #vis_fields #idents: <#types as ::gfx_ne::GpuLayout>::#repr,)*
+                #tail: [u8; #constant.padding(#tail_index)],
+            }
+
+            // SAFETY: every field is `Pod` and all the padding between them is spelled out
+            unsafe impl ::gfx_ne::bytemuck::Zeroable for #target {}
+            unsafe impl ::gfx_ne::bytemuck::Pod for #target {}
+
+            impl ::std::cmp::PartialEq for #target {
+                fn eq(&self, other: &Self) -> bool {
+                    return ::gfx_ne::bytemuck::bytes_of(self) == ::gfx_ne::bytemuck::bytes_of(other);
+                }
+            }
+
+            const _: () = assert!(::std::mem::size_of::<#target>() == #constant.size, #message);
+        });
+    }
+
+    let upper = name.to_string().to_uppercase();
+    let std140 = format_ident!("__{}_STD140", upper);
+    let std430 = format_ident!("__{}_STD430", upper);
+    let std140_target = format_ident!("{}Std140", name);
+    let std430_target = format_ident!("{}Std430", name);
+
+    return Ok(quote! {
+        #(#items)*
+
+        impl ::gfx_ne::GpuLayout for #name {
+            type Std140 = #std140_target;
+            type Std430 = #std430_target;
+            // Derived structs are already padded up to a multiple of their alignment
+            type Std140Element = #std140_target;
+            type Std430Element = #std430_target;
+            const STD140_ALIGN: usize = #std140.align;
+            const STD430_ALIGN: usize = #std430.align;
+
+            fn std140(&self) -> Self::Std140 {
+                let mut std140 = <#std140_target as ::gfx_ne::bytemuck::Zeroable>::zeroed();
+                #(std140.#idents = ::gfx_ne::GpuLayout::std140(&self.#idents);)*
+                return std140;
+            }
+
+            fn std430(&self) -> Self::Std430 {
+                let mut std430 = <#std430_target as ::gfx_ne::bytemuck::Zeroable>::zeroed();
+                #(std430.#idents = ::gfx_ne::GpuLayout::std430(&self.#idents);)*
+                return std430;
+            }
+
+            fn std140_element(&self) -> Self::Std140Element {
+                return ::gfx_ne::GpuLayout::std140(self);
+            }
+
+            fn std430_element(&self) -> Self::Std430Element {
+                return ::gfx_ne::GpuLayout::std430(self);
+            }
+
+            fn wgsl_type(_layout: ::gfx_ne::MemoryLayout) -> ::std::string::String {
+                return #wgsl.to_string();
+            }
+
+            fn wgsl_struct(layout: ::gfx_ne::MemoryLayout) -> ::std::string::String {
+                return match layout {
+                    ::gfx_ne::MemoryLayout::Std140 => #std140.wgsl_struct(#wgsl, [#((
+                        #members,
+                        <#types as ::gfx_ne::GpuLayout>::wgsl_type(layout),
+                        <#types as ::gfx_ne::GpuLayout>::STD430_ALIGN,
+                        <#types as ::gfx_ne::GpuLayout>::STD140_ALIGN,
+                    )),*]),
+                    ::gfx_ne::MemoryLayout::Std430 => #std430.wgsl_struct(#wgsl, [#((
+                        #members,
+                        <#types as ::gfx_ne::GpuLayout>::wgsl_type(layout),
+                        <#types as ::gfx_ne::GpuLayout>::STD430_ALIGN,
+                        <#types as ::gfx_ne::GpuLayout>::STD430_ALIGN,
+                    )),*]),
+                };
+            }
+        }
+    });
+}
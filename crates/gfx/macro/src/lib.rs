@@ -1 +1,156 @@
+use quote::{format_ident, quote};
 use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, Fields, ItemStruct};
+
+mod layout;
+mod vertex;
+mod bind_group;
+
+/// Turn a struct into a cheap to clone handle around an `Arc` of its fields
+///
+/// `#[arcanize] pub struct Sampler { .. }` becomes `SamplerInner` holding the fields,
+/// and `Sampler` wrapping an `Arc<SamplerInner>` that derefs to it. Objects are built
+/// as `SamplerInner { .. }.into()`. Clones share the same object, so equality and
+/// hashing go by pointer rather than by the fields. `Debug` is generated for both,
+/// every field has to implement it, and the struct can't derive it itself.
+///
+/// Only for objects that never change once made, so far `Sampler` and `Texture`.
+/// `Shader` reloads in place through `&mut self`, which a shared handle can't do.
+#[proc_macro_attribute]
+pub fn arcanize(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return syn::Error::new_spanned(attr, "arcanize doesn't take any arguments").to_compile_error().into();
+    }
+
+    let input = parse_macro_input!(item as ItemStruct);
+    if !matches!(input.fields, Fields::Named(_)) {
+        return syn::Error::new_spanned(&input.ident, "arcanize only works on structs with named fields").to_compile_error().into();
+    }
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let label = name.to_string();
+    let inner = format_ident!("{}Inner", name);
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let generics = &input.generics;
+    let fields = &input.fields;
+    let field_names = fields.iter().map(|field| field.ident.as_ref().unwrap());
+
+    // Docs describe the handle people use, everything else like derives belongs to the fields
+    let (docs, attrs): (Vec<_>, Vec<_>) = input.attrs.iter().partition(|attr| attr.path().is_ident("doc"));
+    let inner_doc = format!("The fields of a [`{}`], shared between its clones", name);
+
+    return quote! {
+        #[doc = #inner_doc]
+        #(#attrs)*
+        #vis struct #inner #generics #where_clause #fields
+
+        #(#docs)*
+        #vis struct #name #generics #where_clause {
+            inner: ::std::sync::Arc<#inner #type_generics>,
+        }
+
+        impl #impl_generics ::std::clone::Clone for #name #type_generics #where_clause {
+            fn clone(&self) -> Self {
+                return Self { inner: self.inner.clone() };
+            }
+        }
+
+        impl #impl_generics ::std::ops::Deref for #name #type_generics #where_clause {
+            type Target = #inner #type_generics;
+
+            fn deref(&self) -> &Self::Target {
+                return &self.inner;
+            }
+        }
+
+        impl #impl_generics ::std::convert::From<#inner #type_generics> for #name #type_generics #where_clause {
+            fn from(inner: #inner #type_generics) -> Self {
+                return Self { inner: ::std::sync::Arc::new(inner) };
+            }
+        }
+
+        impl #impl_generics ::std::fmt::Debug for #inner #type_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(#label)
+                    #(.field(stringify!(#field_names), &self.#field_names))*
+                .finish()
+            }
+        }
+
+        impl #impl_generics ::std::fmt::Debug for #name #type_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                return ::std::fmt::Debug::fmt(&*self.inner, f);
+            }
+        }
+
+        impl #impl_generics ::std::cmp::PartialEq for #name #type_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                return ::std::sync::Arc::ptr_eq(&self.inner, &other.inner);
+            }
+        }
+
+        impl #impl_generics ::std::cmp::Eq for #name #type_generics #where_clause {}
+
+        impl #impl_generics ::std::hash::Hash for #name #type_generics #where_clause {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                ::std::hash::Hash::hash(&::std::sync::Arc::as_ptr(&self.inner), state);
+            }
+        }
+    }.into();
+}
+
+/// Implement `BindGroupState` from attributes on the struct's fields
+///
+/// Fields are bound with `#[uniform]`, `#[storage]`, `#[texture]` or `#[sampler]`,
+/// optionally given their binding first, otherwise it follows the previous one:
+///
+/// ```ignore
+/// #[derive(BindGroup)]
+/// #[bind_group(label = "Material Bind Group")]
+/// pub struct MaterialBindGroup {
+///     #[uniform(0, dynamic, label = "Material")]
+///     pub u_material: UniformArena<MaterialUniform>,
+///     #[storage(read_only, visibility(fragment))]
+///     pub lights: StorageBuffer<Light>,
+///     #[texture(dim = "cube", sample_type = "float")]
+///     pub skybox: Arc<Texture>,
+///     #[sampler(filtering)]
+///     pub skybox_sampler: Arc<Sampler>,
+/// }
+/// ```
+///
+/// Bindings are visible to the vertex and fragment stages unless `visibility` says
+/// otherwise. Dynamic uniforms have to be `gfx_ne::UniformArena`s, their offsets come
+/// out of `dynamic_offsets` in binding order. `init` only works when everything is a uniform.
+#[proc_macro_derive(BindGroup, attributes(bind_group, uniform, storage, texture, sampler))]
+pub fn derive_bind_group(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return bind_group::derive(input).unwrap_or_else(syn::Error::into_compile_error).into();
+}
+
+/// Implement `VertexArrayObject`, one attribute per field in declaration order
+///
+/// Formats come from each field's `VertexAttribute` impl, so `Vec3` is `Float32x3` and
+/// `Unorm<[u8; 4]>` is `Unorm8x4`, and `#[format(Float16x2)]` overrides it. Locations
+/// count up from 0, `#[location(n)]` moves a field and the ones after it. Put
+/// `#[step_mode(instance)]` on the struct for per instance data.
+#[proc_macro_derive(VertexLayout, attributes(step_mode, location, format))]
+pub fn derive_vertex_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return vertex::derive(input).unwrap_or_else(syn::Error::into_compile_error).into();
+}
+
+/// Implement `GpuLayout`, generating `NameStd140` and `NameStd430` with all the padding filled in
+///
+/// Fields are written the way Rust wants them, a `Vec3` followed by a `Vec2` needs no
+/// `_padding` field. Both generated structs are `Pod` and come out of `std140()` and
+/// `std430()`, arrays of any `GpuLayout` get the stride their address space asks for.
+/// `wgsl_struct()` declares the matching WGSL struct, named after the Rust one unless
+/// `#[gpu_layout(wgsl = "Name")]` says otherwise.
+#[proc_macro_derive(GpuLayout, attributes(gpu_layout))]
+pub fn derive_gpu_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return layout::derive(input).unwrap_or_else(syn::Error::into_compile_error).into();
+}
@@ -0,0 +1,78 @@
+use quote::quote;
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Error, Fields, Ident, LitInt};
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "VertexLayout can't be derived for generic structs"));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(name, "VertexLayout can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(name, "VertexLayout needs a struct with named fields"));
+    };
+
+    let mut step_mode = quote!(Vertex);
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("step_mode")) {
+        let mode = attr.parse_args::<Ident>()?;
+        step_mode = match mode.to_string().as_str() {
+            "vertex" => quote!(Vertex),
+            "instance" => quote!(Instance),
+            _ => return Err(Error::new_spanned(mode, "expected `vertex` or `instance`")),
+        };
+    }
+
+    // Locations without a number follow the one before them
+    let mut next = 0;
+    let mut checks = Vec::new();
+    let mut attributes = Vec::new();
+    let mut locations = Vec::new();
+    for field in &fields.named {
+        let ty = &field.ty;
+        let ident = field.ident.as_ref().unwrap();
+        let mut location = next;
+        let mut format = quote!(<#ty as ::gfx_ne::VertexAttribute>::FORMAT);
+        for attr in &field.attrs {
+            if attr.path().is_ident("location") {
+                location = attr.parse_args::<LitInt>()?.base10_parse::<u32>()?;
+            } else if attr.path().is_ident("format") {
+                let variant = attr.parse_args::<Ident>()?;
+                format = quote!(::gfx_ne::wgpu::VertexFormat::#variant);
+            }
+        }
+        if locations.contains(&location) {
+            return Err(Error::new_spanned(ident, format!("location {} is used more than once", location)));
+        }
+        locations.push(location);
+        next = location + 1;
+
+        let message = format!("`{}` doesn't match the size of its vertex format", ident);
+        checks.push(quote! {
+            assert!(#format.size() == ::std::mem::size_of::<#ty>() as u64, #message);
+        });
+        attributes.push(quote! {
+            ::gfx_ne::wgpu::VertexAttribute {
+                format: #format,
+                shader_location: #location,
+                offset: ::std::mem::offset_of!(#name, #ident) as ::gfx_ne::wgpu::BufferAddress,
+            }
+        });
+    }
+
+    return Ok(quote! {
+        impl ::gfx_ne::VertexArrayObject for #name {
+            const VERTEX_BUFFER_LAYOUT: ::gfx_ne::wgpu::VertexBufferLayout<'static> = ::gfx_ne::wgpu::VertexBufferLayout {
+                step_mode: ::gfx_ne::wgpu::VertexStepMode::#step_mode,
+                array_stride: ::std::mem::size_of::<#name>() as ::gfx_ne::wgpu::BufferAddress,
+                attributes: &[#(#attributes),*],
+            };
+        }
+
+        // A `#[format]` that reads more or less than the field holds would read the wrong bytes
+        const _: () = {
+            #(#checks)*
+        };
+    });
+}
@@ -0,0 +1,45 @@
+use super::{Diagnostic, Preprocessed, ShaderError, ShaderStage};
+
+/// Parse preprocessed WGSL, reporting errors against the original files
+pub fn parse_wgsl(shader: &Preprocessed) -> Result<naga::Module, ShaderError> {
+    return naga::front::wgsl::parse_str(&shader.source).map_err(|error| {
+        let diagnostic = shader.map.diagnostic(&shader.source, error.message().to_string(), error.labels(), Vec::new());
+        ShaderError::Parse(Box::new(diagnostic))
+    });
+}
+
+/// Parse preprocessed GLSL, only the first error is the primary one
+pub fn parse_glsl(shader: &Preprocessed, stage: ShaderStage) -> Result<naga::Module, ShaderError> {
+    let mut frontend = naga::front::glsl::Frontend::default();
+    let options = naga::front::glsl::Options::from(naga::ShaderStage::from(stage));
+    return frontend.parse(&options, &shader.source).map_err(|errors| {
+        let labels = errors.errors.iter()
+            .map(|error| (error.meta, error.kind.to_string()))
+        .collect::<Vec<_>>();
+        let message = labels.first().map_or(String::from("unknown error"), |(_, label)| label.clone());
+        let labels = labels.iter().map(|(span, label)| (*span, label.as_str()));
+        ShaderError::Parse(Box::new(shader.map.diagnostic(&shader.source, message, labels, Vec::new())))
+    });
+}
+
+/// Run naga's validator over a parsed module without touching the GPU
+///
+/// Spans are only meaningful when the module was parsed from `source`.
+pub fn validate(module: &naga::Module, source: Option<&Preprocessed>) -> Result<naga::valid::ModuleInfo, ShaderError> {
+    let mut validator = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all());
+    return validator.validate(module).map_err(|error| {
+        let mut notes = Vec::new();
+        let mut cause: &dyn std::error::Error = error.as_inner();
+        while let Some(next) = cause.source() {
+            notes.push(next.to_string());
+            cause = next;
+        }
+
+        let message = error.as_inner().to_string();
+        let labels = error.spans().map(|(span, label)| (*span, label.as_str()));
+        ShaderError::Validation(Box::new(match source {
+            Some(shader) => shader.map.diagnostic(&shader.source, message, labels, notes),
+            None => Diagnostic { notes: notes, ..Diagnostic::from(message) },
+        }))
+    });
+}
@@ -0,0 +1,354 @@
+use std::fmt::{Display, Formatter};
+use std::collections::BTreeMap;
+
+/// A set of preprocessor defines a shader is compiled with
+///
+/// Defines are kept sorted so the same set always hashes and prints the same,
+/// no matter what order they were added in.
+#[derive(Debug, Clone, Default)]
+#[derive(Hash, PartialEq, Eq)]
+pub struct ShaderDefines {
+    values: BTreeMap<String, String>,
+}
+
+impl ShaderDefines {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Define a flag, the same as `#define NAME 1`
+    pub fn define(mut self, name: impl Into<String>) -> Self {
+        self.insert(name, "1");
+        return self;
+    }
+
+    /// Define a value, the same as `#define NAME value`
+    pub fn set(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.insert(name, value);
+        return self;
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl ToString) {
+        self.values.insert(name.into(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        return self.values.remove(name);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        return self.values.get(name).map(|value| value.as_str());
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        return self.values.contains_key(name);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.values.is_empty();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        return self.values.iter().map(|(name, value)| (name.as_str(), value.as_str()));
+    }
+
+    /// A stable string identifying this permutation, e.g. `MAX_LIGHTS=64,NORMAL_MAP=1`
+    pub fn key(&self) -> String {
+        return self.to_string();
+    }
+
+    /// Replace every defined identifier in `line` that has a value
+    ///
+    /// Defines without a value (`#define NAME`) are only visible to conditionals.
+    pub(crate) fn substitute(&self, line: &str) -> String {
+        let mut output = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find(is_ident_start) {
+            let (before, word) = rest.split_at(start);
+            let end = word.find(|c: char| !is_ident(c)).unwrap_or(word.len());
+            output.push_str(before);
+            match self.get(&word[..end]) {
+                // Don't touch identifiers that merely end with a define name, `aNORMAL_MAP`
+                Some(value) if !value.is_empty() && !before.ends_with(is_ident) => output.push_str(value),
+                _ => output.push_str(&word[..end]),
+            }
+            rest = &word[end..];
+        }
+        output.push_str(rest);
+        return output;
+    }
+
+    /// Evaluate the expression of an `#if`, anything non-zero is true
+    pub(crate) fn evaluate(&self, expression: &str) -> Result<bool, String> {
+        return Ok(self.evaluate_value(expression, 0)? != 0);
+    }
+
+    fn evaluate_value(&self, expression: &str, depth: u32) -> Result<i64, String> {
+        // Defines can refer to other defines, stop if they end up referring to themselves
+        if depth > 16 {
+            return Err(format!("`{}` expands too deeply", expression.trim()));
+        }
+
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser { tokens: &tokens, position: 0, defines: self, depth: depth };
+        let value = parser.expression(0)?;
+        return match parser.tokens.get(parser.position) {
+            Some(token) => Err(format!("unexpected `{}`", token)),
+            None => Ok(value),
+        };
+    }
+}
+
+impl Display for ShaderDefines {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i != 0 {
+                write!(f, ",")?;
+            }
+            match value.as_str() {
+                "" => write!(f, "{}", name)?,
+                value => write!(f, "{}={}", name, value)?,
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Accepts `NAME` or `NAME=VALUE`, a bare name is defined as `1`
+impl<S: AsRef<str>> FromIterator<S> for ShaderDefines {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut defines = Self::new();
+        for define in iter {
+            match define.as_ref().split_once('=') {
+                Some((name, value)) => defines.insert(name.trim(), value.trim()),
+                None => defines.insert(define.as_ref().trim(), "1"),
+            }
+        }
+        return defines;
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    return c.is_ascii_alphanumeric() || c == '_';
+}
+
+fn is_ident_start(c: char) -> bool {
+    return c.is_ascii_alphabetic() || c == '_';
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Ident(ident) => write!(f, "{}", ident),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+// Longest first so `<<` isn't read as two `<`
+const SYMBOLS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<<", ">>",
+    "(", ")", "!", "~", "+", "-", "*", "/", "%", "<", ">", "&", "|", "^",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() {
+            let end = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+            let literal = rest[..end].trim_end_matches(['u', 'i']);
+            let number = match literal.strip_prefix("0x") {
+                Some(hex) => i64::from_str_radix(hex, 16),
+                None => literal.parse::<i64>(),
+            }.map_err(|_| format!("invalid number `{}`", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if is_ident_start(c) {
+            let end = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            let symbol = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| format!("unexpected `{}`", c))?;
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        }
+        rest = rest.trim_start();
+    }
+    return Ok(tokens);
+}
+
+struct Parser<'t> {
+    depth: u32,
+    position: usize,
+    tokens: &'t [Token],
+    defines: &'t ShaderDefines,
+}
+
+impl<'t> Parser<'t> {
+    fn binding_power(symbol: &str) -> Option<u8> {
+        return match symbol {
+            "||" => Some(1),
+            "&&" => Some(2),
+            "|" => Some(3),
+            "^" => Some(4),
+            "&" => Some(5),
+            "==" | "!=" => Some(6),
+            "<" | "<=" | ">" | ">=" => Some(7),
+            "<<" | ">>" => Some(8),
+            "+" | "-" => Some(9),
+            "*" | "/" | "%" => Some(10),
+            _ => None,
+        };
+    }
+
+    fn next(&mut self) -> Option<&'t Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        return token;
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        return match self.next() {
+            Some(Token::Symbol(s)) if *s == symbol => Ok(()),
+            Some(token) => Err(format!("expected `{}`, found `{}`", symbol, token)),
+            None => Err(format!("expected `{}`", symbol)),
+        };
+    }
+
+    fn expression(&mut self, min_power: u8) -> Result<i64, String> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Symbol(symbol)) = self.tokens.get(self.position) {
+            let Some(power) = Self::binding_power(symbol) else {
+                break;
+            };
+            if power <= min_power {
+                break;
+            }
+            self.position += 1;
+
+            let rhs = self.expression(power)?;
+            lhs = match *symbol {
+                "||" => ((lhs != 0) || (rhs != 0)) as i64,
+                "&&" => ((lhs != 0) && (rhs != 0)) as i64,
+                "|" => lhs | rhs,
+                "^" => lhs ^ rhs,
+                "&" => lhs & rhs,
+                "==" => (lhs == rhs) as i64,
+                "!=" => (lhs != rhs) as i64,
+                "<" => (lhs < rhs) as i64,
+                "<=" => (lhs <= rhs) as i64,
+                ">" => (lhs > rhs) as i64,
+                ">=" => (lhs >= rhs) as i64,
+                "<<" => lhs.wrapping_shl(rhs as u32),
+                ">>" => lhs.wrapping_shr(rhs as u32),
+                "+" => lhs.wrapping_add(rhs),
+                "-" => lhs.wrapping_sub(rhs),
+                "*" => lhs.wrapping_mul(rhs),
+                "/" | "%" if rhs == 0 => return Err(String::from("division by zero")),
+                "/" => lhs.wrapping_div(rhs),
+                "%" => lhs.wrapping_rem(rhs),
+                _ => unreachable!(),
+            };
+        }
+        return Ok(lhs);
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        return match self.next() {
+            Some(Token::Number(number)) => Ok(*number),
+            Some(Token::Symbol("!")) => Ok((self.unary()? == 0) as i64),
+            Some(Token::Symbol("~")) => Ok(!self.unary()?),
+            Some(Token::Symbol("-")) => Ok(self.unary()?.wrapping_neg()),
+            Some(Token::Symbol("+")) => self.unary(),
+            Some(Token::Symbol("(")) => {
+                let value = self.expression(0)?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(Token::Ident(ident)) if ident == "defined" => {
+                // Both `defined(NAME)` and `defined NAME` are accepted
+                let parens = self.tokens.get(self.position) == Some(&Token::Symbol("("));
+                if parens {
+                    self.position += 1;
+                }
+                let name = match self.next() {
+                    Some(Token::Ident(name)) => name,
+                    _ => return Err(String::from("expected a name after `defined`")),
+                };
+                if parens {
+                    self.expect(")")?;
+                }
+                Ok(self.defines.contains(name) as i64)
+            }
+            Some(Token::Ident(ident)) if ident == "true" => Ok(1),
+            Some(Token::Ident(ident)) if ident == "false" => Ok(0),
+            // Like C, unknown names are 0
+            Some(Token::Ident(ident)) => match self.defines.get(ident) {
+                Some("") => Err(format!("`{}` is defined without a value", ident)),
+                Some(value) => self.defines.evaluate_value(value, self.depth + 1),
+                None => Ok(0),
+            },
+            Some(token) => Err(format!("unexpected `{}`", token)),
+            None => Err(String::from("unexpected end of expression")),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precedence() {
+        let defines = ShaderDefines::new();
+        assert_eq!(defines.evaluate_value("1 + 2 * 3", 0), Ok(7));
+        assert_eq!(defines.evaluate_value("(1 + 2) * 3", 0), Ok(9));
+        assert_eq!(defines.evaluate_value("1 << 2 + 1", 0), Ok(8));
+        assert_eq!(defines.evaluate_value("1 | 2 & 0", 0), Ok(1));
+        assert_eq!(defines.evaluate_value("10 - 4 - 3", 0), Ok(3));
+        assert_eq!(defines.evaluate_value("!0 + -1", 0), Ok(0));
+        assert_eq!(defines.evaluate("0 || 1 && 0"), Ok(false));
+        assert_eq!(defines.evaluate("1 == 1 && 2 > 1"), Ok(true));
+    }
+
+    #[test]
+    fn defined() {
+        let defines = ShaderDefines::new().define("SHADOWS").set("MAX_LIGHTS", 4).set("EMPTY", "");
+        assert_eq!(defines.evaluate("defined(SHADOWS) && !defined NORMAL_MAP"), Ok(true));
+        assert_eq!(defines.evaluate("defined(EMPTY)"), Ok(true));
+        assert_eq!(defines.evaluate("MAX_LIGHTS >= 4 && UNDEFINED == 0"), Ok(true));
+        assert!(defines.evaluate("EMPTY").is_err());
+        assert!(defines.evaluate("defined()").is_err());
+        assert!(defines.evaluate("defined(SHADOWS").is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let defines = ShaderDefines::new().set("ZERO", 0).set("SELF", "SELF + 1");
+        assert_eq!(defines.evaluate("1 / 0"), Err(String::from("division by zero")));
+        assert_eq!(defines.evaluate("1 % ZERO"), Err(String::from("division by zero")));
+        assert!(defines.evaluate("SELF").unwrap_err().contains("expands too deeply"));
+        assert!(defines.evaluate("(1").is_err());
+        assert!(defines.evaluate("1 2").is_err());
+        assert!(defines.evaluate("").is_err());
+        assert!(defines.evaluate("1 @ 2").is_err());
+    }
+
+    #[test]
+    fn substitute_whole_words() {
+        let defines = ShaderDefines::new().set("MAX_LIGHTS", 4).define("FLAG").set("EMPTY", "");
+        assert_eq!(defines.substitute("array<Light, MAX_LIGHTS>"), "array<Light, 4>");
+        assert_eq!(defines.substitute("let x = MAX_LIGHTS_X + aMAX_LIGHTS + MAX_LIGHTS;"), "let x = MAX_LIGHTS_X + aMAX_LIGHTS + 4;");
+        assert_eq!(defines.substitute("if FLAG { EMPTY }"), "if 1 { EMPTY }");
+    }
+}
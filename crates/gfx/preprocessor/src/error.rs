@@ -0,0 +1,188 @@
+use std::path::PathBuf;
+use std::fmt::{Display, Formatter};
+
+/// A position in one of the files that went into a shader
+#[derive(Debug, Clone)]
+#[derive(PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    /// Set when the file was imported by module name, e.g. `pregen::camera`
+    pub module: Option<String>,
+    /// 1-based line in `path`
+    pub line: u32,
+    /// 1-based column in bytes
+    pub column: u32,
+    /// Length of the highlighted region in bytes
+    pub length: u32,
+    /// The full line the location points into
+    pub snippet: String,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+/// A compiler error pointing back into the original shader files
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    pub label: Option<String>,
+    pub notes: Vec<String>,
+    pub location: Option<SourceLocation>,
+    pub related: Vec<(SourceLocation, String)>,
+}
+
+/// A diagnostic with only a message, for errors that can't be traced to a location
+impl From<String> for Diagnostic {
+    fn from(message: String) -> Self {
+        return Self {
+            label: None,
+            location: None,
+            message: message,
+            notes: Vec::new(),
+            related: Vec::new(),
+        };
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Some(location) = &self.location else {
+            write!(f, "{}", self.message)?;
+            for note in &self.notes {
+                write!(f, "\n  = note: {}", note)?;
+            }
+            return Ok(());
+        };
+
+        let gutter = location.line.to_string().len();
+        writeln!(f, "{}: {}", location, self.message)?;
+        writeln!(f, "{:gutter$} |", "")?;
+        writeln!(f, "{} | {}", location.line, location.snippet.trim_end())?;
+        write!(f, "{:gutter$} | {:indent$}{}", "", "", "^".repeat(location.length as usize), indent = location.column as usize - 1)?;
+        if let Some(label) = &self.label {
+            write!(f, " {}", label)?;
+        }
+        // Modules only registered from a string already use their name as the path
+        if let Some(module) = location.module.as_ref().filter(|module| location.path.as_os_str() != module.as_str()) {
+            write!(f, "\n  = in module {}", module)?;
+        }
+        for (location, label) in &self.related {
+            write!(f, "\n  = {}: {}", location, label)?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  = note: {}", note)?;
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    /// The shader or one of its imports could not be read
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+        imported_from: Option<PathBuf>,
+    },
+    /// An `#import` directive that does not name a file
+    InvalidImport {
+        line: usize,
+        path: PathBuf,
+        text: String,
+    },
+    /// An `#import` of a module name that isn't registered
+    UnknownModule {
+        line: usize,
+        path: PathBuf,
+        name: String,
+    },
+    /// A malformed `#define`, `#if` or other preprocessor directive, or a bad line in a roots file
+    InvalidDirective {
+        line: usize,
+        path: PathBuf,
+        message: String,
+    },
+    /// A file ends up importing itself, `chain` starts and ends with the same file
+    CircularImport {
+        chain: Vec<PathBuf>,
+    },
+    /// The file extension does not map to a supported shader format
+    UnsupportedFormat {
+        path: PathBuf,
+    },
+    /// A GLSL shader whose stage can't be told from the extension or a `#pragma shader_stage`
+    UnknownStage {
+        path: PathBuf,
+    },
+    /// Defines given to a precompiled shader, which never goes through the preprocessor
+    UnsupportedDefines {
+        path: PathBuf,
+    },
+    /// The expanded source is not valid shader code
+    Parse(Box<Diagnostic>),
+    /// The shader parsed but was rejected by naga or the device
+    Validation(Box<Diagnostic>),
+    /// A naga backend could not write the shader out
+    Translation {
+        target: &'static str,
+        message: String,
+    },
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderError::Io { path, error, imported_from: Some(parent) } => {
+                write!(f, "Failed to read {:?} imported from {:?}: {}", path, parent, error)
+            }
+            ShaderError::Io { path, error, imported_from: None } => {
+                write!(f, "Failed to read shader file {:?}: {}", path, error)
+            }
+            ShaderError::InvalidImport { line, path, text } => {
+                write!(f, "{}:{}: expected a path after #import, found `{}`", path.display(), line, text)
+            }
+            ShaderError::UnknownModule { line, path, name } => {
+                write!(f, "{}:{}: no shader module named `{}`", path.display(), line, name)
+            }
+            ShaderError::InvalidDirective { line, path, message } => {
+                write!(f, "{}:{}: {}", path.display(), line, message)
+            }
+            ShaderError::CircularImport { chain } => {
+                let chain = chain.iter()
+                    .map(|path| path.display().to_string())
+                .collect::<Vec<_>>();
+                write!(f, "Circular import: {}", chain.join(" -> "))
+            }
+            ShaderError::UnsupportedFormat { path } => {
+                write!(f, "Unsupported shader format: {:?}", path)
+            }
+            ShaderError::UnknownStage { path } => {
+                write!(f, "Unknown stage for {:?}, use a .vert/.frag/.comp extension or #pragma shader_stage(...)", path)
+            }
+            ShaderError::UnsupportedDefines { path } => {
+                write!(f, "Can't apply defines to {:?}, precompiled shaders aren't preprocessed", path)
+            }
+            ShaderError::Parse(diagnostic) => {
+                write!(f, "Failed to parse shader: {}", diagnostic)
+            }
+            ShaderError::Validation(diagnostic) => {
+                write!(f, "Failed to validate shader: {}", diagnostic)
+            }
+            ShaderError::Translation { target, message } => {
+                write!(f, "Failed to translate shader to {}: {}", target, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShaderError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
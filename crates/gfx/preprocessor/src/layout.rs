@@ -0,0 +1,134 @@
+/// The two ways host shareable data is laid out
+///
+/// WGSL uses the same rules everywhere, the uniform address space just requires
+/// structs and arrays to start on 16 bytes and array strides to be multiples of 16.
+/// Following those for every uniform gives std140, storage buffers get the tighter std430.
+#[derive(Debug, Clone, Copy)]
+#[derive(Hash, PartialEq, Eq)]
+pub enum MemoryLayout {
+    /// `var<uniform>`
+    Std140,
+    /// `var<storage>`
+    Std430,
+}
+
+impl MemoryLayout {
+    /// Put members given as `(align, size)` after each other, writing where each one starts to `offsets`
+    ///
+    /// Returns the alignment and size of the struct holding them. Std430 is how
+    /// WGSL lays out any struct without `@align` or `@size` attributes.
+    pub const fn place(self, members: &[(usize, usize)], offsets: &mut [usize]) -> (usize, usize) {
+        let mut i = 0;
+        let mut end: usize = 0;
+        let mut align = 1;
+        while i < members.len() {
+            offsets[i] = end.next_multiple_of(members[i].0);
+            end = offsets[i] + members[i].1;
+            if members[i].0 > align {
+                align = members[i].0;
+            }
+            i += 1;
+        }
+
+        if let MemoryLayout::Std140 = self {
+            align = align.next_multiple_of(16);
+        }
+        return (align, end.next_multiple_of(align));
+    }
+}
+
+/// Where each member of a derived struct goes, worked out at compile time
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct StructLayout<const N: usize> {
+    pub offsets: [usize; N],
+    pub sizes: [usize; N],
+    pub align: usize,
+    pub size: usize,
+}
+
+impl<const N: usize> StructLayout<N> {
+    /// Lay out members given as `(align, size)` in declaration order
+    pub const fn new(members: [(usize, usize); N], layout: MemoryLayout) -> Self {
+        let mut i = 0;
+        let mut sizes = [0; N];
+        while i < N {
+            sizes[i] = members[i].1;
+            i += 1;
+        }
+
+        let mut offsets = [0; N];
+        let (align, size) = layout.place(&members, &mut offsets);
+        return Self {
+            sizes: sizes,
+            offsets: offsets,
+            align: align,
+            size: size,
+        };
+    }
+
+    /// The padding in front of member `i`, or at the end of the struct when `i` is `N`
+    pub const fn padding(&self, i: usize) -> usize {
+        let end = match i {
+            0 => 0,
+            _ => self.offsets[i - 1] + self.sizes[i - 1],
+        };
+        return match i == N {
+            true => self.size - end,
+            false => self.offsets[i] - end,
+        };
+    }
+
+    /// Declare the struct in WGSL, members are `(name, type, natural alignment, alignment)`
+    ///
+    /// Members that need more alignment than WGSL gives them get an `@align`, after
+    /// which WGSL puts every member at the same offset. Only a std140 struct's size
+    /// can still be off, so the last member is stretched with `@size` to match.
+    pub fn wgsl_struct(&self, name: &str, members: [(&str, String, usize, usize); N]) -> String {
+        // What WGSL will make the struct's alignment, which std140 might round up further
+        let struct_align = members.iter().map(|member| member.3).max().unwrap_or(1);
+        let mut wgsl = format!("struct {} {{\n", name);
+        for (i, (member, ty, natural, align)) in members.iter().enumerate() {
+            let mut attributes = String::new();
+            if align != natural {
+                attributes.push_str(&format!("@align({}) ", align));
+            }
+            if i == N - 1 && (self.offsets[i] + self.sizes[i]).next_multiple_of(struct_align) != self.size {
+                attributes.push_str(&format!("@size({}) ", self.size - self.offsets[i]));
+            }
+            wgsl.push_str(&format!("    {}{}: {},\n", attributes, member, ty));
+        }
+        wgsl.push_str("}\n");
+        return wgsl;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place() {
+        // vec3<f32>, f32, vec2<f32>
+        let members = [(16, 12), (4, 4), (8, 8)];
+        let mut offsets = [0; 3];
+        assert_eq!(MemoryLayout::Std430.place(&members, &mut offsets), (16, 32));
+        assert_eq!(offsets, [0, 12, 16]);
+        assert_eq!(MemoryLayout::Std140.place(&members, &mut offsets), (16, 32));
+
+        // f32, vec2<f32>, uniform structs are rounded up to 16 bytes
+        let members = [(4, 4), (8, 8)];
+        let mut offsets = [0; 2];
+        assert_eq!(MemoryLayout::Std430.place(&members, &mut offsets), (8, 16));
+        assert_eq!(MemoryLayout::Std140.place(&members, &mut offsets), (16, 16));
+        assert_eq!(MemoryLayout::Std140.place(&[(4, 4)], &mut offsets), (16, 16));
+    }
+
+    #[test]
+    fn padding() {
+        let layout = StructLayout::new([(4, 4), (16, 12), (4, 4)], MemoryLayout::Std140);
+        assert_eq!(layout.offsets, [0, 16, 28]);
+        assert_eq!((0..=3).map(|i| layout.padding(i)).collect::<Vec<_>>(), vec![0, 12, 0, 0]);
+        assert_eq!(layout.size, 32);
+    }
+}
@@ -0,0 +1,51 @@
+//! The shader preprocessor and naga front end shared by gfx-ne and build scripts
+//!
+//! Only depends on naga, so generating code from shaders doesn't pull in wgpu.
+//! Struct layout lives here too, so derived and generated structs agree on it.
+
+mod error;
+mod layout;
+mod compiler;
+mod defines;
+mod registry;
+mod source_map;
+mod preprocessor;
+
+pub use source_map::SourceMap;
+pub use layout::{MemoryLayout, StructLayout};
+pub use defines::ShaderDefines;
+pub use registry::ShaderRegistry;
+pub use compiler::{parse_glsl, parse_wgsl, validate};
+pub use error::{Diagnostic, ShaderError, SourceLocation};
+pub use preprocessor::{Preprocessed, Preprocessor};
+
+#[derive(Eq, PartialEq, Hash)]
+#[derive(Ord, PartialOrd)]
+#[derive(Debug, Copy, Clone)]
+pub enum ShaderStage {
+    Vertex,
+    Compute,
+    Fragment,
+}
+
+impl ShaderStage {
+    /// The stage implied by a GLSL file extension like `.vert`
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        return match extension {
+            "vert" => Some(ShaderStage::Vertex),
+            "comp" => Some(ShaderStage::Compute),
+            "frag" => Some(ShaderStage::Fragment),
+            _ => None,
+        };
+    }
+}
+
+impl From<ShaderStage> for naga::ShaderStage {
+    fn from(stage: ShaderStage) -> Self {
+        return match stage {
+            ShaderStage::Vertex => naga::ShaderStage::Vertex,
+            ShaderStage::Compute => naga::ShaderStage::Compute,
+            ShaderStage::Fragment => naga::ShaderStage::Fragment,
+        };
+    }
+}
@@ -0,0 +1,428 @@
+use std::fs;
+use super::compiler;
+use super::registry::Module;
+use super::{ShaderDefines, ShaderError, ShaderRegistry, ShaderStage, SourceMap};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// The flattened source of a shader and every file that went into it
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    pub source: String,
+    /// Every file included, dependencies come before the files importing them
+    ///
+    /// Modules registered from a string aren't files, so they are left out.
+    pub imports: Vec<PathBuf>,
+    /// Where each line of `source` originally came from
+    pub map: SourceMap,
+    /// Defines in effect at the end, including ones set by the shader itself
+    pub defines: ShaderDefines,
+    /// Set by `#pragma shader_stage(...)`
+    pub stage: Option<ShaderStage>,
+}
+
+impl Preprocessed {
+    /// Parse and validate the source with naga, errors point into the original files
+    pub fn compile(&self) -> Result<(naga::Module, naga::valid::ModuleInfo), ShaderError> {
+        let module = compiler::parse_wgsl(self)?;
+        let info = compiler::validate(&module, Some(self))?;
+        return Ok((module, info));
+    }
+
+    /// The same as `compile` but for GLSL, which needs to know the stage up front
+    pub fn compile_glsl(&self, stage: ShaderStage) -> Result<(naga::Module, naga::valid::ModuleInfo), ShaderError> {
+        let module = compiler::parse_glsl(self, stage)?;
+        let info = compiler::validate(&module, Some(self))?;
+        return Ok((module, info));
+    }
+}
+
+// An open `#if`, `#ifdef` or `#ifndef` block
+#[derive(Debug)]
+struct Conditional {
+    line: usize,
+    // Whether lines in the current branch are kept
+    active: bool,
+    // Whether any branch so far has been kept
+    taken: bool,
+    // Whether the block containing this one is kept
+    parent: bool,
+    seen_else: bool,
+}
+
+/// Expands `#import`, `#define` and conditional directives into a single source
+///
+/// Each file is included exactly once no matter how many times it is
+/// imported, and before any file that imports it. A define is visible to
+/// everything processed after it, including files imported later on.
+/// Imports are paths relative to the importing file, or module names like
+/// `pregen::camera` looked up in a `ShaderRegistry`.
+#[derive(Debug, Default)]
+pub struct Preprocessor {
+    map: SourceMap,
+    defines: ShaderDefines,
+    output: Vec<String>,
+    imports: Vec<PathBuf>,
+    // Uses the global registry when not set
+    registry: Option<ShaderRegistry>,
+    stage: Option<ShaderStage>,
+    // The first `#version` seen and where it came from, GLSL needs it on the first line
+    version: Option<(PathBuf, u32, String)>,
+    // Files currently being expanded, used to report the import chain on cycles
+    stack: Vec<(PathBuf, PathBuf)>,
+    included: HashSet<PathBuf>,
+}
+
+impl Preprocessor {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Start with `defines` already set, as if they were defined before the first line
+    pub fn with_defines(defines: ShaderDefines) -> Self {
+        return Self {
+            defines: defines,
+            ..Self::default()
+        };
+    }
+
+    /// Resolve module imports with `registry` instead of the global one
+    pub fn registry(mut self, registry: ShaderRegistry) -> Self {
+        self.registry = Some(registry);
+        return self;
+    }
+
+    pub fn process(mut self, path: impl AsRef<Path>) -> Result<Preprocessed, ShaderError> {
+        self.include(path.as_ref(), None, None, None)?;
+        if let Some((path, line, version)) = self.version.take() {
+            let file = self.map.files.iter().position(|file| file == &path).unwrap();
+            self.map.prepend_line(file, line);
+            self.output.insert(0, version);
+        }
+
+        return Ok(Preprocessed {
+            stage: self.stage,
+            source: self.output.join("\n"),
+            imports: self.imports,
+            map: self.map,
+            defines: self.defines,
+        });
+    }
+
+    /// Include a file, or a module registered from a string when `source` is given
+    fn include(&mut self, path: &Path, module: Option<&str>, source: Option<String>, parent: Option<&Path>) -> Result<(), ShaderError> {
+        // Symlinks and `../` would otherwise make the same file look like two different ones
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some(index) = self.stack.iter().position(|(entry, _)| entry == &key) {
+            let mut chain = self.stack[index..].iter()
+                .map(|(_, path)| path.clone())
+            .collect::<Vec<_>>();
+            chain.push(path.to_path_buf());
+            return Err(ShaderError::CircularImport { chain: chain });
+        }
+        if self.included.contains(&key) {
+            log::trace!("Skipping {:?}, already included", path);
+            return Ok(());
+        }
+
+        let is_virtual = source.is_some();
+        let source = match source {
+            Some(source) => source,
+            None => fs::read_to_string(path).map_err(|error| ShaderError::Io {
+                error: error,
+                path: path.to_path_buf(),
+                imported_from: parent.map(|p| p.to_path_buf()),
+            })?,
+        };
+
+        self.stack.push((key.clone(), path.to_path_buf()));
+        let mut body = Vec::new();
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        for (i, line) in source.lines().enumerate() {
+            let active = conditionals.last().is_none_or(|conditional| conditional.active);
+            let Some((directive, rest)) = Self::parse_directive(line) else {
+                if active {
+                    body.push((i as u32 + 1, self.defines.substitute(line)));
+                }
+                continue;
+            };
+
+            let error = |message: String| ShaderError::InvalidDirective {
+                line: i + 1,
+                message: message,
+                path: path.to_path_buf(),
+            };
+            match directive {
+                "if" | "ifdef" | "ifndef" => {
+                    // Don't evaluate anything inside a disabled block, it may not make sense
+                    let enabled = active && match directive {
+                        "ifdef" => self.defines.contains(Self::parse_name(rest).map_err(error)?),
+                        "ifndef" => !self.defines.contains(Self::parse_name(rest).map_err(error)?),
+                        _ => self.defines.evaluate(rest).map_err(error)?,
+                    };
+                    conditionals.push(Conditional {
+                        line: i + 1,
+                        parent: active,
+                        seen_else: false,
+                        active: enabled,
+                        taken: enabled,
+                    });
+                }
+                "elif" | "else" => {
+                    let Some(conditional) = conditionals.last_mut() else {
+                        return Err(error(format!("#{} without a matching #if", directive)));
+                    };
+                    if conditional.seen_else {
+                        return Err(error(format!("#{} after #else", directive)));
+                    }
+                    let enabled = conditional.parent && !conditional.taken && match directive {
+                        "elif" => self.defines.evaluate(rest).map_err(error)?,
+                        _ => true,
+                    };
+                    conditional.seen_else = directive == "else";
+                    conditional.taken |= enabled;
+                    conditional.active = enabled;
+                }
+                "endif" => {
+                    if conditionals.pop().is_none() {
+                        return Err(error(String::from("#endif without a matching #if")));
+                    }
+                }
+                _ if !active => continue,
+                "define" => {
+                    let (name, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    let name = Self::parse_name(name).map_err(error)?;
+                    // Expand now so a define can refer to the ones before it
+                    let value = self.defines.substitute(value.trim());
+                    self.defines.insert(name, value);
+                }
+                "version" => match &self.version {
+                    None => self.version = Some((path.to_path_buf(), i as u32 + 1, line.trim().to_string())),
+                    Some((_, _, version)) if version != line.trim() => {
+                        log::warn!("{}:{}: ignoring `{}`, already using `{}`", path.display(), i + 1, line.trim(), version);
+                    }
+                    Some(_) => {}
+                },
+                "pragma" if rest.starts_with("shader_stage") => {
+                    let stage = rest.strip_prefix("shader_stage").unwrap().trim();
+                    self.stage = Some(match stage {
+                        "(vertex)" => ShaderStage::Vertex,
+                        "(fragment)" => ShaderStage::Fragment,
+                        "(compute)" => ShaderStage::Compute,
+                        _ => return Err(error(format!("unknown shader stage `{}`", stage))),
+                    });
+                }
+                "undef" => {
+                    self.defines.remove(Self::parse_name(rest).map_err(error)?);
+                }
+                "import" => {
+                    if rest.is_empty() {
+                        return Err(ShaderError::InvalidImport {
+                            line: i + 1,
+                            path: path.to_path_buf(),
+                            text: line.trim().to_string(),
+                        });
+                    }
+                    if !ShaderRegistry::is_module_name(rest) {
+                        self.include(&Self::resolve(path, rest), None, None, Some(path))?;
+                        continue;
+                    }
+
+                    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("wgsl");
+                    let module = match &self.registry {
+                        Some(registry) => registry.resolve(rest, extension),
+                        None => ShaderRegistry::global().read().unwrap().resolve(rest, extension),
+                    };
+                    match module {
+                        Some(Module::File(file)) => self.include(&file, Some(rest), None, Some(path))?,
+                        Some(Module::Virtual(source)) => self.include(Path::new(rest), Some(rest), Some(source), Some(path))?,
+                        None => return Err(ShaderError::UnknownModule {
+                            line: i + 1,
+                            path: path.to_path_buf(),
+                            name: rest.to_string(),
+                        }),
+                    }
+                }
+                // Not ours, leave it for whatever consumes the source
+                _ => body.push((i as u32 + 1, self.defines.substitute(line))),
+            }
+        }
+        if let Some(conditional) = conditionals.last() {
+            return Err(ShaderError::InvalidDirective {
+                line: conditional.line,
+                path: path.to_path_buf(),
+                message: String::from("#if without a matching #endif"),
+            });
+        }
+        self.stack.pop();
+
+        self.included.insert(key);
+        let file = self.map.add_file(path, module);
+        if !is_virtual {
+            self.imports.push(path.to_path_buf());
+        }
+        for (number, line) in body {
+            self.map.add_line(file, number);
+            self.output.push(line);
+        }
+        return Ok(());
+    }
+
+    /// Split a line like `#define NAME 1` into the directive and its arguments
+    fn parse_directive(line: &str) -> Option<(&str, &str)> {
+        let line = line.trim().strip_prefix('#')?;
+        // Comments are allowed after directives, but can't end up in a define
+        let line = line.split_once("//").map_or(line, |(line, _)| line);
+        let (directive, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = match directive {
+            "import" => rest.trim().trim_matches(|c| c == '"' || c == '<' || c == '>'),
+            _ => rest.trim(),
+        };
+        return Some((directive, rest));
+    }
+
+    fn parse_name(text: &str) -> Result<&str, String> {
+        let valid = text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        return match valid {
+            true => Ok(text),
+            false => Err(format!("expected a name, found `{}`", text)),
+        };
+    }
+
+    fn resolve(source: &Path, import: &str) -> PathBuf {
+        // Imports are relative to the file doing the importing
+        return match source.parent() {
+            Some(parent) => parent.join(import),
+            None => PathBuf::from(import),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `files` to a fresh directory for the test called `name`
+    fn shader_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gfx-preprocessor-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, source) in files {
+            fs::write(dir.join(file), source).unwrap();
+        }
+        return dir;
+    }
+
+    #[test]
+    fn circular_import_reports_the_chain() {
+        let dir = shader_dir("cycle", &[
+            ("main.wgsl", "#import \"a.wgsl\""),
+            ("a.wgsl", "#import \"b.wgsl\""),
+            ("b.wgsl", "#import \"a.wgsl\""),
+        ]);
+        let error = Preprocessor::new().process(dir.join("main.wgsl")).unwrap_err();
+        let ShaderError::CircularImport { chain } = error else {
+            panic!("expected a circular import, got {:?}", error);
+        };
+        assert_eq!(chain, vec![dir.join("a.wgsl"), dir.join("b.wgsl"), dir.join("a.wgsl")]);
+    }
+
+    #[test]
+    fn circular_module_import() {
+        let mut registry = ShaderRegistry::new();
+        registry.register("test::a", "#import test::b");
+        registry.register("test::b", "#import test::a");
+        let dir = shader_dir("module-cycle", &[("main.wgsl", "#import test::a")]);
+        let error = Preprocessor::new().registry(registry).process(dir.join("main.wgsl")).unwrap_err();
+        let ShaderError::CircularImport { chain } = error else {
+            panic!("expected a circular import, got {:?}", error);
+        };
+        assert_eq!(chain, vec![PathBuf::from("test::a"), PathBuf::from("test::b"), PathBuf::from("test::a")]);
+    }
+
+    #[test]
+    fn diamond_imports_are_included_once() {
+        let dir = shader_dir("diamond", &[
+            ("main.wgsl", "#import \"left.wgsl\"\n#import \"right.wgsl\"\nfn main() {}"),
+            ("left.wgsl", "#import \"common.wgsl\"\nfn left() {}"),
+            ("right.wgsl", "#import \"common.wgsl\"\nfn right() {}"),
+            ("common.wgsl", "fn common() {}"),
+        ]);
+        let shader = Preprocessor::new().process(dir.join("main.wgsl")).unwrap();
+        assert_eq!(shader.source, "fn common() {}\nfn left() {}\nfn right() {}\nfn main() {}");
+        let imports = ["common.wgsl", "left.wgsl", "right.wgsl", "main.wgsl"].map(|file| dir.join(file));
+        assert_eq!(shader.imports, imports);
+        assert_eq!(shader.map.lookup(4), Some((dir.join("main.wgsl").as_path(), 3)));
+    }
+
+    #[test]
+    fn modules_from_strings_are_not_imports() {
+        let mut registry = ShaderRegistry::new();
+        registry.register("test::common", "fn common() {}");
+        let dir = shader_dir("virtual", &[("main.wgsl", "#import test::common\nfn main() {}")]);
+        let shader = Preprocessor::new().registry(registry).process(dir.join("main.wgsl")).unwrap();
+        assert_eq!(shader.source, "fn common() {}\nfn main() {}");
+        assert_eq!(shader.imports, vec![dir.join("main.wgsl")]);
+        assert_eq!(shader.map.modules[0].as_deref(), Some("test::common"));
+    }
+
+    #[test]
+    fn import_without_a_path() {
+        let dir = shader_dir("invalid-import", &[("main.wgsl", "fn main() {}\n#import \"\"")]);
+        let error = Preprocessor::new().process(dir.join("main.wgsl")).unwrap_err();
+        let ShaderError::InvalidImport { line, path, text } = error else {
+            panic!("expected an invalid import, got {:?}", error);
+        };
+        assert_eq!((line, path, text.as_str()), (2, dir.join("main.wgsl"), "#import \"\""));
+    }
+
+    #[test]
+    fn missing_import() {
+        let dir = shader_dir("missing", &[("main.wgsl", "#import \"missing.wgsl\"")]);
+        let error = Preprocessor::new().process(dir.join("main.wgsl")).unwrap_err();
+        let ShaderError::Io { path, imported_from, .. } = error else {
+            panic!("expected an io error, got {:?}", error);
+        };
+        assert_eq!((path, imported_from), (dir.join("missing.wgsl"), Some(dir.join("main.wgsl"))));
+    }
+
+    #[test]
+    fn unknown_module() {
+        let dir = shader_dir("unknown-module", &[("main.wgsl", "#import test::missing")]);
+        let error = Preprocessor::new().registry(ShaderRegistry::new()).process(dir.join("main.wgsl")).unwrap_err();
+        assert!(matches!(error, ShaderError::UnknownModule { line: 1, ref name, .. } if name == "test::missing"), "{:?}", error);
+    }
+
+    #[test]
+    fn conditionals() {
+        let source = "#define LIGHTS 2\n#if LIGHTS > 3\nmany\n#elif LIGHTS > 1 && defined(SHADOWS)\nshadowed\n#elif LIGHTS > 1\nsome\n#else\nnone\n#endif\n#ifndef SHADOWS\nunshadowed\n#endif";
+        let dir = shader_dir("conditionals", &[("main.wgsl", source)]);
+        let shader = Preprocessor::new().process(dir.join("main.wgsl")).unwrap();
+        assert_eq!(shader.source, "some\nunshadowed");
+        assert_eq!(shader.map.lookup(2), Some((dir.join("main.wgsl").as_path(), 12)));
+
+        let shader = Preprocessor::with_defines(ShaderDefines::new().define("SHADOWS")).process(dir.join("main.wgsl")).unwrap();
+        assert_eq!(shader.source, "shadowed");
+    }
+
+    #[test]
+    fn mismatched_conditionals() {
+        let cases = [
+            ("unterminated", "#if 1\n#if 0\n#endif", 1, "#if without a matching #endif"),
+            ("stray-else", "fn main() {}\n#else", 2, "#else without a matching #if"),
+            ("stray-elif", "#elif 1", 1, "#elif without a matching #if"),
+            ("stray-endif", "#if 1\n#endif\n#endif", 3, "#endif without a matching #if"),
+            ("elif-after-else", "#if 0\n#else\n#elif 1\n#endif", 3, "#elif after #else"),
+            ("division-by-zero", "#if 1 / 0\n#endif", 1, "division by zero"),
+        ];
+        for (name, source, expected_line, expected_message) in cases {
+            let dir = shader_dir(name, &[("main.wgsl", source)]);
+            let error = Preprocessor::new().process(dir.join("main.wgsl")).unwrap_err();
+            let ShaderError::InvalidDirective { line, message, .. } = error else {
+                panic!("{}: expected an invalid directive, got {:?}", name, error);
+            };
+            assert_eq!((line, message.as_str()), (expected_line, expected_message), "{}", name);
+        }
+    }
+}
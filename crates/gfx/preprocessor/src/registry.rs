@@ -0,0 +1,144 @@
+use std::sync::{LazyLock, RwLock};
+use std::collections::HashMap;
+use super::ShaderError;
+use std::path::{Path, PathBuf};
+
+static GLOBAL: LazyLock<RwLock<ShaderRegistry>> = LazyLock::new(RwLock::default);
+
+/// Shader modules that can be imported by name, like `#import pregen::camera`
+///
+/// A name resolves to a module registered from a string first, then to a file
+/// under the root registered for its first segment, so `pregen::lights::point`
+/// is `point.wgsl` in `lights/` under the `pregen` root.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderRegistry {
+    roots: HashMap<String, PathBuf>,
+    modules: HashMap<String, String>,
+}
+
+/// Where an imported module name ended up
+#[derive(Debug, Clone)]
+pub(crate) enum Module {
+    File(PathBuf),
+    Virtual(String),
+}
+
+impl ShaderRegistry {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// The registry used by any preprocessor that wasn't given one
+    pub fn global() -> &'static RwLock<ShaderRegistry> {
+        return &GLOBAL;
+    }
+
+    /// Resolve `namespace::...` to files under `directory`
+    pub fn add_root(&mut self, namespace: impl Into<String>, directory: impl Into<PathBuf>) {
+        self.roots.insert(namespace.into(), directory.into());
+    }
+
+    /// Add every root listed in `path`, one `NAME = DIR` per line
+    ///
+    /// Directories are relative to the file and `#` starts a comment, that way one
+    /// file can be shared by the app, build scripts and the `shaders` tool.
+    pub fn add_roots_from(&mut self, path: impl AsRef<Path>) -> Result<(), ShaderError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|error| ShaderError::Io {
+            error: error,
+            imported_from: None,
+            path: path.to_path_buf(),
+        })?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((namespace, directory)) = line.split_once('=') else {
+                return Err(ShaderError::InvalidDirective {
+                    line: i + 1,
+                    path: path.to_path_buf(),
+                    message: format!("expected `NAME = DIR`, found `{}`", line),
+                });
+            };
+            self.add_root(namespace.trim(), base.join(directory.trim()));
+        }
+        return Ok(());
+    }
+
+    /// Every root directory by its namespace
+    pub fn roots(&self) -> impl Iterator<Item = (&str, &Path)> {
+        return self.roots.iter().map(|(namespace, directory)| (namespace.as_str(), directory.as_path()));
+    }
+
+    /// Register a module that only exists in memory, replacing any module with the same name
+    ///
+    /// Shaders already compiled with the old source need to be reloaded to see the change.
+    pub fn register(&mut self, name: impl Into<String>, source: impl Into<String>) {
+        self.modules.insert(name.into(), source.into());
+    }
+
+    pub fn unregister(&mut self, name: &str) -> Option<String> {
+        return self.modules.remove(name);
+    }
+
+    /// Whether `import` names a module rather than a relative path
+    pub(crate) fn is_module_name(import: &str) -> bool {
+        return import.contains("::");
+    }
+
+    /// Find a module, files get the same extension as the shader importing them
+    pub(crate) fn resolve(&self, name: &str, extension: &str) -> Option<Module> {
+        if let Some(source) = self.modules.get(name) {
+            return Some(Module::Virtual(source.clone()));
+        }
+
+        let mut segments = name.split("::");
+        let root = self.roots.get(segments.next()?)?;
+        let path = segments.fold(root.clone(), |path, segment| path.join(segment));
+        let path = path.with_extension(extension);
+        return path.exists().then_some(Module::File(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_before_files() {
+        let dir = std::env::temp_dir().join(format!("gfx-registry-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lights")).unwrap();
+        std::fs::write(dir.join("lights/point.wgsl"), "").unwrap();
+
+        let mut registry = ShaderRegistry::new();
+        registry.add_root("test", &dir);
+        assert!(matches!(registry.resolve("test::lights::point", "wgsl"), Some(Module::File(path)) if path == dir.join("lights/point.wgsl")));
+        assert!(registry.resolve("test::lights::point", "glsl").is_none());
+        assert!(registry.resolve("other::lights::point", "wgsl").is_none());
+
+        registry.register("test::lights::point", "fn point() {}");
+        assert!(matches!(registry.resolve("test::lights::point", "wgsl"), Some(Module::Virtual(source)) if source == "fn point() {}"));
+        registry.unregister("test::lights::point");
+        assert!(matches!(registry.resolve("test::lights::point", "wgsl"), Some(Module::File(_))));
+    }
+
+    #[test]
+    fn roots_file() {
+        let dir = std::env::temp_dir().join(format!("gfx-roots-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("roots"), "# shaders\npregen = shaders # app\n\nlib = ../lib\n").unwrap();
+        std::fs::write(dir.join("bad-roots"), "pregen = shaders\nlib\n").unwrap();
+
+        let mut registry = ShaderRegistry::new();
+        registry.add_roots_from(dir.join("roots")).unwrap();
+        let mut roots = registry.roots().map(|(namespace, path)| (namespace.to_string(), path.to_path_buf())).collect::<Vec<_>>();
+        roots.sort();
+        assert_eq!(roots, vec![(String::from("lib"), dir.join("../lib")), (String::from("pregen"), dir.join("shaders"))]);
+
+        let error = ShaderRegistry::new().add_roots_from(dir.join("bad-roots")).unwrap_err();
+        assert!(matches!(error, ShaderError::InvalidDirective { line: 2, .. }), "{:?}", error);
+    }
+}
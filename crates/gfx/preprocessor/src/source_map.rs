@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+use super::{Diagnostic, SourceLocation};
+
+/// Maps lines of a preprocessed shader back to the files they came from
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    pub files: Vec<PathBuf>,
+    /// The module name each file was imported by, if it was imported by name
+    pub modules: Vec<Option<String>>,
+    // (index into `files`, 1-based line in that file) for every line of the expanded source
+    lines: Vec<(usize, u32)>,
+}
+
+impl SourceMap {
+    pub(crate) fn add_file(&mut self, path: &Path, module: Option<&str>) -> usize {
+        self.files.push(path.to_path_buf());
+        self.modules.push(module.map(String::from));
+        return self.files.len() - 1;
+    }
+
+    pub(crate) fn add_line(&mut self, file: usize, line: u32) {
+        self.lines.push((file, line));
+    }
+
+    pub(crate) fn prepend_line(&mut self, file: usize, line: u32) {
+        self.lines.insert(0, (file, line));
+    }
+
+    /// Find the original file and line for a 1-based line of the expanded source
+    pub fn lookup(&self, line: u32) -> Option<(&Path, u32)> {
+        let (file, line) = self.lines.get(line.checked_sub(1)? as usize)?;
+        return Some((self.files[*file].as_path(), *line));
+    }
+
+    /// Resolve a span of the expanded source to its original location
+    pub fn locate(&self, source: &str, span: naga::Span) -> Option<SourceLocation> {
+        let range = span.to_range()?;
+        if range.end > source.len() {
+            return None;
+        }
+
+        let location = span.location(source);
+        let (file, line) = *self.lines.get(location.line_number.checked_sub(1)? as usize)?;
+        let snippet = source.lines().nth(location.line_number as usize - 1).unwrap_or("");
+        return Some(SourceLocation {
+            line: line,
+            path: self.files[file].clone(),
+            module: self.modules[file].clone(),
+            snippet: snippet.to_string(),
+            column: location.line_position,
+            // Highlight at most until the end of the line
+            length: (location.length as usize).min(snippet.len().saturating_sub(location.line_position as usize - 1)).max(1) as u32,
+        });
+    }
+
+    /// Build a diagnostic whose primary location is the first labelled span
+    pub fn diagnostic<'s>(&self, source: &str, message: String, mut labels: impl Iterator<Item = (naga::Span, &'s str)>, notes: Vec<String>) -> Diagnostic {
+        let primary = labels.next();
+        return Diagnostic {
+            notes: notes,
+            message: message,
+            location: primary.and_then(|(span, _)| self.locate(source, span)),
+            label: primary.map(|(_, label)| label.to_string()).filter(|label| !label.is_empty()),
+            related: labels.filter_map(|(span, label)| Some((self.locate(source, span)?, label.to_string()))).collect(),
+        };
+    }
+
+    /// Rewrite `wgsl:LINE:COL` references in a report produced by naga or wgpu
+    pub fn rewrite(&self, report: &str) -> String {
+        const MARKER: &str = "wgsl:";
+        let mut output = String::with_capacity(report.len());
+        let mut rest = report;
+        while let Some(index) = rest.find(MARKER) {
+            output.push_str(&rest[..index]);
+            rest = &rest[index + MARKER.len()..];
+
+            let line_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let mapped = rest[..line_end].parse::<u32>().ok()
+                .filter(|_| rest[line_end..].starts_with(':'))
+                .and_then(|line| self.lookup(line));
+            match mapped {
+                Some((path, line)) => {
+                    output.push_str(&format!("{}:{}", path.display(), line));
+                    rest = &rest[line_end..];
+                }
+                None => output.push_str(MARKER),
+            }
+        }
+        output.push_str(rest);
+        return output;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Preprocessor, ShaderError};
+
+    #[test]
+    fn error_in_an_import() {
+        let dir = std::env::temp_dir().join(format!("gfx-source-map-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.wgsl"), "#import \"lights.wgsl\"\nfn main() -> f32 { return light(); }").unwrap();
+        std::fs::write(dir.join("lights.wgsl"), "// Lights\n#define SCALE 1.0\nfn light() -> f32 {\n    return SCALE +;\n}").unwrap();
+
+        let shader = Preprocessor::new().process(dir.join("main.wgsl")).unwrap();
+        let error = shader.compile().unwrap_err();
+        let ShaderError::Parse(diagnostic) = error else {
+            panic!("expected a parse error, got {:?}", error);
+        };
+        let location = diagnostic.location.unwrap();
+        assert_eq!((location.path, location.line, location.column), (dir.join("lights.wgsl"), 4, 17));
+        // The snippet is the line as compiled, after defines were substituted
+        assert_eq!(location.snippet, "    return 1.0 +;");
+
+        let report = shader.map.rewrite("error: at wgsl:3:17 and wgsl:5:1, not wgsl:99:1 or wgsl:x");
+        let expected = format!("error: at {}:4:17 and {}:2:1, not wgsl:99:1 or wgsl:x", dir.join("lights.wgsl").display(), dir.join("main.wgsl").display());
+        assert_eq!(report, expected);
+    }
+}
@@ -0,0 +1,380 @@
+//! Offline shader tooling, nothing in here touches the GPU
+//!
+//! ```text
+//! shaders check [-D NAME[=VALUE]]... [PATH]...
+//! shaders translate --target spirv|hlsl|msl|glsl [-o DIR] [OPTIONS] FILE...
+//! ```
+//!
+//! `check` expands and validates every entry shader under each path, `src/shaders`
+//! by default. Files only ever imported by other shaders, or under a module root,
+//! are checked through the shaders importing them.
+//!
+//! `#import NAME::...` modules resolve the way they do in the app, through the
+//! roots listed in a `roots` file next to each path, see `ShaderRegistry::add_roots_from`.
+//! Both commands also take `--root NAME=DIR`, any number of times, e.g.
+//! `--root pregen=src/shaders/types`, which wins over the files.
+//!
+//! `translate` writes each file out with one of naga's backends, to `DIR/NAME.EXT`
+//! or stdout. Target options are `--spirv-version 1.0`, `--hlsl-model 5.1`,
+//! `--msl-version 1.0`, `--glsl-version 310` (GLSL ES) and `--stage vertex`,
+//! GLSL translates every stage in the file unless one is given.
+use std::io::Write;
+use std::process::ExitCode;
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use gfx_ne::naga::back::{glsl, hlsl, msl, spv};
+use gfx_ne::{Preprocessor, Reflection, Shader, ShaderDefines, ShaderError, ShaderRegistry, ShaderStage, Target};
+
+const DEFAULT_PATH: &str = "src/shaders";
+const ROOTS_FILE: &str = "roots";
+const EXTENSIONS: &[&str] = &["wgsl", "vert", "frag", "comp", "glsl", "spv"];
+const USAGE: &str = "Usage: shaders check [-D NAME[=VALUE]]... [--root NAME=DIR]... [PATH]...
+       shaders translate --target spirv|hlsl|msl|glsl [-D NAME[=VALUE]]... [--root NAME=DIR]... [-o DIR] [OPTIONS] FILE...";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let mut args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {}\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    if let Err(error) = args.load_roots() {
+        eprintln!("error: {}", error);
+        return ExitCode::FAILURE;
+    }
+    // Shader::validate resolves modules through the global registry
+    *ShaderRegistry::global().write().unwrap() = args.registry.clone();
+
+    return match command.as_deref() {
+        Some("check") => check(args),
+        Some("translate") => translate(args),
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    };
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    paths: Vec<PathBuf>,
+    defines: ShaderDefines,
+    registry: ShaderRegistry,
+    /// From `--root`, added after any `roots` files
+    roots: Vec<(String, PathBuf)>,
+    options: HashMap<String, String>,
+    /// `-h` or `--help` anywhere after the command
+    help: bool,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut defines = Vec::new();
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            if arg == "--root" {
+                let root = args.next().ok_or("--root expects NAME=DIR")?;
+                let (name, directory) = root.split_once('=').ok_or(format!("expected NAME=DIR, found `{}`", root))?;
+                parsed.roots.push((name.to_string(), PathBuf::from(directory)));
+            } else if let Some(define) = arg.strip_prefix("-D") {
+                match define {
+                    "" => defines.push(args.next().ok_or("-D expects a define")?),
+                    define => defines.push(define.to_string()),
+                }
+            } else if arg == "-h" || arg == "--help" {
+                parsed.help = true;
+            } else if arg == "-o" || arg.starts_with("--") {
+                let value = args.next().ok_or(format!("{} expects a value", arg))?;
+                parsed.options.insert(arg.trim_start_matches('-').to_string(), value);
+            } else {
+                parsed.paths.push(PathBuf::from(arg));
+            }
+        }
+        parsed.defines = defines.iter().collect();
+        return Ok(parsed);
+    }
+
+    /// Fill the registry from the `roots` file next to each path, then from `--root`
+    fn load_roots(&mut self) -> Result<(), ShaderError> {
+        let paths = match self.paths.is_empty() {
+            true => vec![PathBuf::from(DEFAULT_PATH)],
+            false => self.paths.clone(),
+        };
+
+        let mut loaded = HashSet::new();
+        for path in paths {
+            let directory = match path.is_dir() {
+                true => path.as_path(),
+                false => path.parent().unwrap_or(Path::new("")),
+            };
+            let file = directory.join(ROOTS_FILE);
+            if file.is_file() && loaded.insert(file.clone()) {
+                self.registry.add_roots_from(&file)?;
+            }
+        }
+        for (name, directory) in &self.roots {
+            self.registry.add_root(name.as_str(), directory.as_path());
+        }
+        return Ok(());
+    }
+
+    /// Take an option out, so anything left over at the end wasn't understood
+    fn take(&mut self, name: &str) -> Option<String> {
+        return self.options.remove(name);
+    }
+}
+
+fn check(mut args: Args) -> ExitCode {
+    if let Some(option) = args.options.keys().next() {
+        eprintln!("error: unknown option {}\n{}", option, USAGE);
+        return ExitCode::from(2);
+    }
+    if args.paths.is_empty() {
+        args.paths.push(PathBuf::from(DEFAULT_PATH));
+    }
+    let (paths, defines) = (args.paths, args.defines);
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if let Err(error) = collect(path, &mut files) {
+            eprintln!("error: failed to read {}: {}", path.display(), error);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut failed = 0;
+    // A broken import fails every shader using it, only say so once
+    let mut reported = HashSet::new();
+    let entries = entries(&files, &defines, &args.registry);
+    for file in &entries {
+        if let Err(error) = Shader::validate(file, defines.clone()) {
+            failed += 1;
+            if reported.insert(error.to_string()) {
+                report(file, &error);
+            }
+        }
+    }
+
+    match failed {
+        0 => println!("checked {} shader(s), no errors", entries.len()),
+        _ => eprintln!("checked {} shader(s), {} failed", entries.len(), failed),
+    }
+    return match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    };
+}
+
+fn translate(mut args: Args) -> ExitCode {
+    let (target, stage) = match parse_target(&mut args) {
+        Ok(target) => target,
+        Err(error) => {
+            eprintln!("error: {}\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let output = args.take("o").map(PathBuf::from);
+    if let Some(option) = args.options.keys().next() {
+        eprintln!("error: unknown option {}\n{}", option, USAGE);
+        return ExitCode::from(2);
+    }
+    if args.paths.is_empty() {
+        eprintln!("error: expected a shader to translate\n{}", USAGE);
+        return ExitCode::from(2);
+    }
+
+    let mut failed = false;
+    for file in &args.paths {
+        let (module, info) = match Shader::validate(file, args.defines.clone()) {
+            Ok(validated) => validated,
+            Err(error) => {
+                report(file, &error);
+                failed = true;
+                continue;
+            }
+        };
+
+        // GLSL gets a file per stage unless it was told which one it wants
+        let targets = match (&target, stage) {
+            (Target::Glsl(options, _), None) => {
+                let mut stages = Reflection::new(&module, &info).entry_points.iter()
+                    .map(|entry| entry.stage)
+                .collect::<Vec<_>>();
+                stages.sort();
+                stages.dedup();
+                stages.into_iter().map(|stage| Target::Glsl(options.clone(), stage)).collect()
+            }
+            (Target::Glsl(options, _), Some(stage)) => vec![Target::Glsl(options.clone(), stage)],
+            (target, _) => vec![target.clone()],
+        };
+
+        for target in &targets {
+            let translation = match gfx_ne::translate(&module, &info, target) {
+                Ok(translation) => translation,
+                Err(error) => {
+                    eprintln!("{}: {}\n", file.display(), error);
+                    failed = true;
+                    continue;
+                }
+            };
+
+            let result = match &output {
+                Some(directory) => {
+                    let name = file.file_stem().unwrap_or_default().to_string_lossy();
+                    let path = directory.join(format!("{}.{}", name, target.extension()));
+                    std::fs::create_dir_all(directory)
+                        .and_then(|_| std::fs::write(&path, translation.to_bytes()))
+                        .map(|_| println!("wrote {}", path.display()))
+                }
+                None => std::io::stdout().write_all(&translation.to_bytes()),
+            };
+            if let Err(error) = result {
+                eprintln!("error: failed to write {} output for {}: {}", target.name(), file.display(), error);
+                failed = true;
+            }
+        }
+    }
+
+    return match failed {
+        false => ExitCode::SUCCESS,
+        true => ExitCode::FAILURE,
+    };
+}
+
+/// The target and its options, GLSL's stage is only a placeholder unless `--stage` was given
+fn parse_target(args: &mut Args) -> Result<(Target, Option<ShaderStage>), String> {
+    let stage = match args.take("stage").as_deref() {
+        Some("vertex") => Some(ShaderStage::Vertex),
+        Some("fragment") => Some(ShaderStage::Fragment),
+        Some("compute") => Some(ShaderStage::Compute),
+        Some(stage) => return Err(format!("unknown stage `{}`", stage)),
+        None => None,
+    };
+
+    let target = match args.take("target").as_deref() {
+        Some("spirv") => {
+            let mut options = spv::Options::default();
+            if let Some(version) = args.take("spirv-version") {
+                options.lang_version = parse_version(&version)?;
+            }
+            Target::Spirv(options)
+        }
+        Some("hlsl") => {
+            let mut options = hlsl::Options::default();
+            if let Some(model) = args.take("hlsl-model") {
+                options.shader_model = match model.as_str() {
+                    "5.0" => hlsl::ShaderModel::V5_0,
+                    "5.1" => hlsl::ShaderModel::V5_1,
+                    "6.0" => hlsl::ShaderModel::V6_0,
+                    "6.1" => hlsl::ShaderModel::V6_1,
+                    "6.2" => hlsl::ShaderModel::V6_2,
+                    "6.3" => hlsl::ShaderModel::V6_3,
+                    "6.4" => hlsl::ShaderModel::V6_4,
+                    "6.5" => hlsl::ShaderModel::V6_5,
+                    "6.6" => hlsl::ShaderModel::V6_6,
+                    "6.7" => hlsl::ShaderModel::V6_7,
+                    _ => return Err(format!("unknown shader model `{}`", model)),
+                };
+            }
+            Target::Hlsl(options)
+        }
+        Some("msl") => {
+            let mut options = msl::Options::default();
+            if let Some(version) = args.take("msl-version") {
+                options.lang_version = parse_version(&version)?;
+            }
+            Target::Msl(options)
+        }
+        Some("glsl") => {
+            let mut options = glsl::Options::default();
+            if let Some(version) = args.take("glsl-version") {
+                let version = version.parse().map_err(|_| format!("invalid GLSL ES version `{}`", version))?;
+                options.version = glsl::Version::new_gles(version);
+            }
+            Target::Glsl(options, stage.unwrap_or(ShaderStage::Vertex))
+        }
+        Some(target) => return Err(format!("unknown target `{}`", target)),
+        None => return Err(String::from("--target is required")),
+    };
+    return Ok((target, stage));
+}
+
+/// `1.3` to `(1, 3)`
+fn parse_version(version: &str) -> Result<(u8, u8), String> {
+    let invalid = || format!("invalid version `{}`, expected MAJOR.MINOR", version);
+    let (major, minor) = version.split_once('.').ok_or_else(invalid)?;
+    return Ok((major.parse().map_err(|_| invalid())?, minor.parse().map_err(|_| invalid())?));
+}
+
+/// Every shader file under `path`, sorted so output is stable between runs
+fn collect(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut children = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<Result<Vec<_>, _>>()?;
+    children.sort();
+    for child in children {
+        let extension = child.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        if child.is_dir() {
+            collect(&child, files)?;
+        } else if EXTENSIONS.contains(&extension) {
+            files.push(child);
+        }
+    }
+    return Ok(());
+}
+
+/// Drop files that other shaders import or that live under a module root, they aren't meant to compile on their own
+fn entries(files: &[PathBuf], defines: &ShaderDefines, registry: &ShaderRegistry) -> Vec<PathBuf> {
+    let canonical = |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let roots = registry.roots().map(|(_, directory)| canonical(directory)).collect::<Vec<_>>();
+
+    let mut imported = HashSet::new();
+    for file in files {
+        // Files that don't preprocess are reported when they are checked
+        let Ok(shader) = Preprocessor::with_defines(defines.clone()).process(file) else {
+            continue;
+        };
+        imported.extend(shader.imports.iter()
+            .map(|import| canonical(import))
+            .filter(|import| *import != canonical(file))
+        );
+    }
+    return files.iter()
+        .filter(|file| !imported.contains(&canonical(file)))
+        .filter(|file| !roots.iter().any(|root| canonical(file).starts_with(root)))
+        .cloned()
+    .collect();
+}
+
+fn report(file: &Path, error: &ShaderError) {
+    match error {
+        // These already start with `file:line:col`
+        ShaderError::Parse(diagnostic) | ShaderError::Validation(diagnostic) if diagnostic.location.is_some() => {
+            eprintln!("{}\n", diagnostic);
+        }
+        ShaderError::Parse(diagnostic) | ShaderError::Validation(diagnostic) => {
+            eprintln!("{}: {}\n", file.display(), diagnostic);
+        }
+        ShaderError::InvalidDirective { .. } | ShaderError::InvalidImport { .. } | ShaderError::UnknownModule { .. } => {
+            eprintln!("{}\n", error);
+        }
+        _ => eprintln!("{}: {}\n", file.display(), error),
+    }
+}
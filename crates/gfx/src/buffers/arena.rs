@@ -0,0 +1,219 @@
+use bytemuck::Pod;
+use std::num::NonZeroU64;
+use super::StagingBelt;
+
+/// Many `T`s packed into one uniform buffer, each bound by its own dynamic offset
+///
+/// Every slot starts on `min_uniform_buffer_offset_alignment`, so a single bind group
+/// can serve every object, with the slot picked per draw through `set_bind_group`'s
+/// offsets. Growing reallocates the buffer and bumps `generation`, bind groups using
+/// it have to be recreated. This is what `#[uniform(dynamic)]` bindings are derived for.
+pub struct UniformArena<T> {
+    pub name: String,
+    pub buffer: wgpu::Buffer,
+    stride: u64,
+    generation: u64,
+    device: wgpu::Device,
+    values: Vec<T>,
+    dirty: Vec<bool>,
+    free: Vec<usize>,
+}
+
+impl<T: Pod + PartialEq> UniformArena<T> {
+    /// How many slots an arena starts with, enough for a small scene without growing
+    const INITIAL_CAPACITY: usize = 64;
+
+    pub fn new(device: &wgpu::Device, name: Option<&str>) -> Self {
+        let name = name.unwrap_or("Unnamed Arena");
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = (size_of::<T>() as u64).next_multiple_of(alignment);
+        return Self {
+            stride: stride,
+            generation: 0,
+            device: device.clone(),
+            name: name.to_string(),
+            values: Vec::with_capacity(Self::INITIAL_CAPACITY),
+            dirty: Vec::with_capacity(Self::INITIAL_CAPACITY),
+            free: Vec::new(),
+            buffer: Self::create_buffer(device, name, stride * Self::INITIAL_CAPACITY as u64),
+        };
+    }
+
+    /// Take a slot for `data`, reusing one that was removed if there is any
+    pub fn insert(&mut self, data: T) -> usize {
+        if let Some(slot) = self.free.pop() {
+            self.values[slot] = data;
+            self.dirty[slot] = true;
+            return slot;
+        }
+
+        self.values.push(data);
+        self.dirty.push(true);
+        if self.values.len() as u64 * self.stride > self.buffer.size() {
+            // The old contents are still on the CPU, so they're uploaded again instead of copied
+            let size = (self.buffer.size() * 2).max(self.stride);
+            log::debug!("Growing {} to {} bytes", self.name, size);
+            self.buffer = Self::create_buffer(&self.device, &self.name, size);
+            self.dirty.fill(true);
+            self.generation += 1;
+        }
+        return self.values.len() - 1;
+    }
+
+    /// Give a slot back, its offset may be handed out again by `insert`
+    pub fn remove(&mut self, slot: usize) {
+        debug_assert!(!self.free.contains(&slot), "Slot {} of {} was removed twice", slot, self.name);
+        self.dirty[slot] = false;
+        self.free.push(slot);
+    }
+
+    pub fn set(&mut self, slot: usize, data: T) {
+        if self.values[slot] != data {
+            self.values[slot] = data;
+            self.dirty[slot] = true;
+        }
+    }
+
+    pub fn get(&self, slot: usize) -> &T {
+        return &self.values[slot];
+    }
+
+    /// The dynamic offset to bind `slot` with
+    pub fn offset(&self, slot: usize) -> u32 {
+        return (slot as u64 * self.stride) as u32;
+    }
+
+    /// Bumped every time the buffer is reallocated
+    pub fn generation(&self) -> u64 {
+        return self.generation;
+    }
+
+    /// Send every slot that changed, they reach the GPU when `uploads` is flushed
+    pub fn upload(&mut self, uploads: &mut StagingBelt) {
+        for (slot, dirty) in self.dirty.iter_mut().enumerate() {
+            if *dirty {
+                *dirty = false;
+                uploads.write(&self.buffer, slot as u64 * self.stride, bytemuck::bytes_of(&self.values[slot]));
+            }
+        }
+    }
+
+    /// A single slot sized binding, the offset is supplied per draw
+    pub fn as_binding(&self) -> wgpu::BindingResource<'_> {
+        return wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            offset: 0,
+            buffer: &self.buffer,
+            size: NonZeroU64::new(size_of::<T>() as u64),
+        });
+    }
+
+    fn create_buffer(device: &wgpu::Device, name: &str, size: u64) -> wgpu::Buffer {
+        return device.create_buffer(&wgpu::BufferDescriptor {
+            size: size,
+            label: Some(name),
+            mapped_at_creation: false,
+            // COPY_SRC so the contents can be read back for debugging
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::UNIFORM,
+        });
+    }
+}
+
+impl<T> std::fmt::Debug for UniformArena<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UniformArena")
+            .field("name", &self.name)
+            .field("stride", &self.stride)
+            .field("generation", &self.generation)
+            .field("slots", &self.values.len())
+            .field("free", &self.free.len())
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffers::{read_back, test_device as device};
+
+    #[test]
+    fn removed_slots_are_reused() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut arena = UniformArena::<[u32; 4]>::new(&device.device, Some("Reuse Test"));
+        let slots = [1, 2, 3].map(|i| arena.insert([i; 4]));
+        assert_eq!(slots, [0, 1, 2]);
+        assert_eq!(arena.offset(2), 2 * arena.offset(1));
+        assert!(arena.offset(1).is_multiple_of(device.device.limits().min_uniform_buffer_offset_alignment));
+
+        arena.remove(1);
+        arena.remove(0);
+        assert_eq!(arena.insert([4; 4]), 0);
+        assert_eq!(arena.insert([5; 4]), 1);
+        assert_eq!(arena.insert([6; 4]), 3);
+        assert_eq!((*arena.get(0), *arena.get(1), *arena.get(2)), ([4; 4], [5; 4], [3; 4]));
+        assert_eq!(arena.generation(), 0);
+    }
+
+    #[test]
+    fn growing_uploads_every_slot_again() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut uploads = StagingBelt::new(&device.device, 1024);
+        let mut arena = UniformArena::<[u32; 4]>::new(&device.device, Some("Growth Test"));
+        for i in 0..64 {
+            arena.insert([i; 4]);
+        }
+        arena.upload(&mut uploads);
+        uploads.flush(&device.queue);
+        let size = arena.buffer.size();
+        assert_eq!(arena.generation(), 0);
+
+        // Nothing is dirty anymore until the new buffer needs every slot
+        arena.insert([64; 4]);
+        assert_eq!((arena.generation(), arena.buffer.size()), (1, size * 2));
+        arena.set(3, [30; 4]);
+        arena.upload(&mut uploads);
+        uploads.flush(&device.queue);
+
+        let stride = arena.offset(1) as usize / size_of::<u32>();
+        let words = read_back::<u32>(&device, &arena.buffer, 65 * stride);
+        for slot in 0..65 {
+            let expected = match slot {
+                3 => [30; 4],
+                _ => [slot as u32; 4],
+            };
+            assert_eq!(words[slot * stride..slot * stride + 4], expected, "slot {}", slot);
+        }
+    }
+
+    #[derive(crate::BindGroup)]
+    struct ModelBindGroup {
+        #[uniform(0, dynamic)]
+        model: UniformArena<[f32; 16]>,
+        #[uniform(1, dynamic)]
+        tint: UniformArena<[f32; 4]>,
+    }
+
+    #[test]
+    fn derived_bind_groups_follow_their_arenas() {
+        use crate::BindGroupState;
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut group = ModelBindGroup::init(&device.device);
+        for _ in 0..2 {
+            group.model.insert([0.0; 16]);
+            group.tint.insert([0.0; 4]);
+        }
+        assert_eq!(group.dynamic_offsets(1), vec![group.model.offset(1), group.tint.offset(1)]);
+        for _ in 0..63 {
+            group.tint.insert([1.0; 4]);
+        }
+        assert_eq!(group.generation(), 1);
+    }
+}
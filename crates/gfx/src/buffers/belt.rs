@@ -0,0 +1,119 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// A staging buffer the belt sub-allocates writes from
+#[derive(Debug)]
+struct Chunk {
+    offset: u64,
+    buffer: wgpu::Buffer,
+}
+
+/// Batches small buffer writes into shared staging chunks, copied with a single encoder
+///
+/// Every `write` during a frame lands in a mapped chunk and records a copy into the
+/// target, `flush` then submits all of them at once instead of one `write_buffer`
+/// each. Chunks are remapped once the GPU is done with them and reused, so after the
+/// first few frames nothing is allocated. Writes become visible to anything submitted
+/// after the `flush`.
+#[derive(Debug)]
+pub struct StagingBelt {
+    chunk_size: u64,
+    device: wgpu::Device,
+    /// Mapped and being written this frame
+    active: Vec<Chunk>,
+    /// Mapped and empty, ready to be written again
+    free: Vec<Chunk>,
+    encoder: Option<wgpu::CommandEncoder>,
+    // Chunks come back from the `map_async` callbacks through here
+    sender: Sender<Chunk>,
+    receiver: Receiver<Chunk>,
+}
+
+impl StagingBelt {
+    /// `chunk_size` should fit everything written in a typical frame, bigger writes get their own chunk
+    pub fn new(device: &wgpu::Device, chunk_size: u64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        return Self {
+            sender: sender,
+            receiver: receiver,
+            active: Vec::new(),
+            free: Vec::new(),
+            encoder: None,
+            device: device.clone(),
+            chunk_size: chunk_size.next_multiple_of(wgpu::MAP_ALIGNMENT),
+        };
+    }
+
+    /// Queue a write of `bytes` into `target` at `offset`, copied on the next `flush`
+    ///
+    /// `target` needs `COPY_DST`, and `offset` and the length of `bytes` have to be multiples of 4.
+    pub fn write(&mut self, target: &wgpu::Buffer, offset: u64, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        assert!(offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) && (bytes.len() as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "Staged writes have to be aligned to {} bytes, got {} bytes at {}", wgpu::COPY_BUFFER_ALIGNMENT, bytes.len(), offset);
+
+        let size = bytes.len() as u64;
+        let index = self.allocate(size);
+        if self.encoder.is_none() {
+            self.encoder = Some(self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Staging Belt Encoder"),
+            }));
+        }
+
+        let chunk = &mut self.active[index];
+        chunk.buffer.slice(chunk.offset..chunk.offset + size).get_mapped_range_mut().copy_from_slice(bytes);
+        self.encoder.as_mut().unwrap().copy_buffer_to_buffer(&chunk.buffer, chunk.offset, target, offset, size);
+        chunk.offset = (chunk.offset + size).next_multiple_of(wgpu::MAP_ALIGNMENT);
+    }
+
+    /// Submit every write made since the last flush, does nothing if there weren't any
+    pub fn flush(&mut self, queue: &wgpu::Queue) {
+        let Some(encoder) = self.encoder.take() else {
+            return;
+        };
+
+        // Chunks have to be unmapped before the copies out of them run
+        for chunk in &self.active {
+            chunk.buffer.unmap();
+        }
+        queue.submit(Some(encoder.finish()));
+
+        for mut chunk in self.active.drain(..) {
+            let sender = self.sender.clone();
+            let buffer = chunk.buffer.clone();
+            chunk.offset = 0;
+            buffer.slice(..).map_async(wgpu::MapMode::Write, move |result| match result {
+                // If the receiver is gone the belt was dropped and so can the chunk
+                Ok(()) => drop(sender.send(chunk)),
+                Err(error) => log::warn!("Dropping a staging chunk that failed to map: {}", error),
+            });
+        }
+    }
+
+    /// The index of an active chunk with room for `size` bytes
+    fn allocate(&mut self, size: u64) -> usize {
+        // Anything remapped since the last write can be reused
+        self.free.extend(self.receiver.try_iter());
+
+        let fits = |chunk: &Chunk| chunk.offset + size <= chunk.buffer.size();
+        if let Some(index) = self.active.iter().position(fits) {
+            return index;
+        }
+
+        let chunk = match self.free.iter().position(fits) {
+            Some(index) => self.free.swap_remove(index),
+            None => Chunk {
+                offset: 0,
+                buffer: self.device.create_buffer(&wgpu::BufferDescriptor {
+                    mapped_at_creation: true,
+                    label: Some("Staging Belt Chunk"),
+                    size: self.chunk_size.max(size.next_multiple_of(wgpu::MAP_ALIGNMENT)),
+                    usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                }),
+            },
+        };
+        self.active.push(chunk);
+        return self.active.len() - 1;
+    }
+}
@@ -1,8 +1,32 @@
+use bytemuck::Pod;
 use std::marker::PhantomData;
-use bytemuck::{Pod, Zeroable};
 use std::sync::atomic::{Ordering, AtomicU64};
-use crate::{Device, WgpuExt, Dynamic, Buffer};
+use super::{Buffer, BufferType, Element, Shadow};
+use crate::{Device, Dynamic};
 
+/// What an index buffer can hold, `u16`, `u32` or groups of them like `[u32; 3]` for triangles
+pub trait IndexType: Pod {
+    const FORMAT: wgpu::IndexFormat;
+    /// How many indices a single element is
+    const INDICES: usize;
+}
+
+impl IndexType for u16 {
+    const INDICES: usize = 1;
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
+}
+
+impl IndexType for u32 {
+    const INDICES: usize = 1;
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint32;
+}
+
+impl<T: IndexType, const N: usize> IndexType for [T; N] where [T; N]: Pod {
+    const FORMAT: wgpu::IndexFormat = T::FORMAT;
+    const INDICES: usize = T::INDICES * N;
+}
+
+#[repr(C)]
 pub struct IndexBuffer<T> {
     pub count: usize,
     pub device: Device,
@@ -11,6 +35,9 @@ pub struct IndexBuffer<T> {
     phantom: PhantomData<T>,
     pub buffer: wgpu::Buffer,
     pub label: Option<String>,
+    /// What `T` is once erased to `Dynamic`, private so `downcast` can trust it
+    element: Element,
+    shadow: Shadow,
     pub format: wgpu::IndexFormat,
 }
 
@@ -23,9 +50,95 @@ impl<T> IndexBuffer<T> {
         return self.generation.load(Ordering::Acquire);
     }
 
-    pub unsafe fn transmute<U>(self) -> IndexBuffer<U> {
-        let this = std::mem::ManuallyDrop::new(self);
-        return std::mem::transmute_copy(&*this);
+    /// Forget `T`, the element layout stays available through `element`
+    pub fn erase(self) -> IndexBuffer<Dynamic> {
+        return IndexBuffer {
+            count: self.count,
+            label: self.label,
+            device: self.device,
+            buffer: self.buffer,
+            format: self.format,
+            element: self.element,
+            shadow: self.shadow,
+            capacity: self.capacity,
+            generation: self.generation,
+            phantom: PhantomData,
+        };
+    }
+}
+
+impl<T: IndexType> IndexBuffer<T> {
+    pub const USAGE: wgpu::BufferUsages = wgpu::BufferUsages::INDEX
+        .union(wgpu::BufferUsages::COPY_DST)
+    .union(wgpu::BufferUsages::COPY_SRC);
+
+    /// An empty buffer with room for `capacity` elements
+    pub fn new(device: &Device, label: Option<&str>, capacity: usize) -> Self {
+        return Self {
+            count: 0,
+            capacity: capacity,
+            format: T::FORMAT,
+            device: device.clone(),
+            phantom: PhantomData,
+            generation: AtomicU64::new(0),
+            element: Element::of::<T>(),
+            shadow: Shadow::new(size_of::<T>()),
+            label: label.map(String::from),
+            buffer: super::create(device, label, Self::USAGE, capacity * size_of::<T>()),
+        };
+    }
+
+    pub fn from_slice(device: &Device, label: Option<&str>, data: &[T]) -> Self {
+        let mut buffer = Self::new(device, label, data.len());
+        buffer.write(data);
+        return buffer;
+    }
+
+    /// Replace every element, growing if `data` doesn't fit
+    pub fn write(&mut self, data: &[T]) {
+        self.count = 0;
+        self.reserve(data.len());
+        self.shadow.clear();
+        super::write(&self.device, &self.buffer, 0, bytemuck::cast_slice(data), &mut self.shadow);
+        self.count = data.len();
+    }
+
+    /// Overwrite elements starting at `offset`, anything past the end is appended
+    pub fn write_range(&mut self, offset: usize, data: &[T]) {
+        assert!(offset <= self.count, "Writing indices at {} would leave a gap after the {} in {:?}", offset, self.count, self.label);
+        self.reserve(offset + data.len());
+        super::write(&self.device, &self.buffer, offset * size_of::<T>(), bytemuck::cast_slice(data), &mut self.shadow);
+        self.count = self.count.max(offset + data.len());
+    }
+
+    pub fn push(&mut self, data: &[T]) {
+        self.write_range(self.count, data);
+    }
+
+    /// Make room for `count` elements, keeping the current ones
+    ///
+    /// Growing replaces the wgpu buffer and bumps the generation.
+    pub fn reserve(&mut self, count: usize) {
+        if count <= self.capacity {
+            return;
+        }
+
+        let capacity = super::grown_capacity(self.capacity, count);
+        self.buffer = super::reallocate(&self.device, &self.buffer, self.label.as_deref(), self.count * size_of::<T>(), capacity * size_of::<T>());
+        self.capacity = capacity;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// The number of indices to draw, which differs from `count` when elements are groups
+    pub fn index_count(&self) -> u32 {
+        return (self.count * T::INDICES) as u32;
+    }
+
+    /// The part of the buffer holding indices, for `set_index_buffer`
+    ///
+    /// wgpu can't slice nothing, so this is `None` while the buffer is empty.
+    pub fn slice(&self) -> Option<wgpu::BufferSlice<'_>> {
+        return (self.count != 0).then(|| self.buffer.slice(..(self.count * size_of::<T>()) as u64));
     }
 }
 
@@ -37,24 +150,106 @@ impl<T> AsRef<wgpu::Buffer> for IndexBuffer<T> {
 
 impl<T> AsRef<IndexBuffer<Dynamic>> for IndexBuffer<T> {
     fn as_ref(&self) -> &IndexBuffer<Dynamic> {
-        unsafe { std::mem::transmute(self) }
+        // SAFETY: the struct is `repr(C)` and `T` only appears in a `PhantomData`,
+        // so every `IndexBuffer<T>` has the same layout
+        return unsafe { &*(self as *const Self as *const IndexBuffer<Dynamic>) };
     }
 }
 
-#[allow(unused)]
-fn test(device: Device) {
-    // If any "attribute" is unknown it is defined at runtime
-    // If known this is a compile-time constant and can be used
+impl IndexBuffer<Dynamic> {
+    /// Get the typed buffer back if it really holds `U`, otherwise the buffer itself, boxed
+    pub fn downcast<U: 'static>(self) -> Result<IndexBuffer<U>, Box<Self>> {
+        if !self.element.is::<U>() {
+            return Err(Box::new(self));
+        }
+        return Ok(IndexBuffer {
+            count: self.count,
+            label: self.label,
+            device: self.device,
+            buffer: self.buffer,
+            format: self.format,
+            element: self.element,
+            shadow: self.shadow,
+            capacity: self.capacity,
+            generation: self.generation,
+            phantom: PhantomData,
+        });
+    }
 
-    // known type; unknown size
-    let _: IndexBuffer<u16>;
+    pub fn downcast_ref<U: 'static>(&self) -> Option<&IndexBuffer<U>> {
+        // SAFETY: same layout as above, and the element type was checked
+        return self.element.is::<U>().then(|| unsafe { &*(self as *const Self as *const IndexBuffer<U>) });
+    }
+}
 
-    // unknown type; unknown size
-    let _: IndexBuffer<Dynamic>;
+impl<T> Buffer for IndexBuffer<T> {
+    fn size(&self) -> usize {
+        return self.buffer.size() as usize;
+    }
 
-    // known type; known size
-    let _: IndexBuffer<[u32; 3]>;
+    fn label(&self) -> String {
+        return self.label.clone().unwrap_or_default();
+    }
 
-    // unknown type; known size
-    let _: IndexBuffer<[Dynamic; 6]>;
+    fn generation(&self) -> u64 {
+        return self.generation.load(Ordering::Acquire);
+    }
+
+    fn r#type(&self) -> BufferType {
+        return BufferType::Index;
+    }
+
+    fn buffer(&self) -> &wgpu::Buffer {
+        return &self.buffer;
+    }
+
+    fn usage(&self) -> wgpu::BufferUsages {
+        return self.buffer.usage();
+    }
+
+    fn element(&self) -> Element {
+        return self.element;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffers::{read_back, test_device as device};
+
+    #[test]
+    fn triangles_grow_and_bump_the_generation() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        // 6 byte triangles, so every other one starts halfway through a word
+        let mut buffer = IndexBuffer::<[u16; 3]>::new(&device, Some("Index Growth Test"), 1);
+        assert_eq!(buffer.format, wgpu::IndexFormat::Uint16);
+        buffer.push(&[[0, 1, 2]]);
+        assert_eq!((buffer.capacity, buffer.generation()), (1, 0));
+        buffer.push(&[[2, 3, 0], [4, 5, 6]]);
+        assert_eq!((buffer.count, buffer.capacity, buffer.generation()), (3, 4, 1));
+        assert_eq!(buffer.index_count(), 9);
+
+        buffer.write_range(1, &[[7, 8, 9]]);
+        assert_eq!(read_back::<[u16; 3]>(&device, &buffer.buffer, 3), vec![[0, 1, 2], [7, 8, 9], [4, 5, 6]]);
+    }
+
+    #[test]
+    fn write_replaces_everything() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut buffer = IndexBuffer::<u32>::from_slice(&device, Some("Index Write Test"), &[0, 1, 2, 3]);
+        buffer.write(&[5, 6]);
+        assert_eq!((buffer.count, buffer.capacity, buffer.generation()), (2, 4, 0));
+        assert_eq!(buffer.slice().unwrap().size().get(), 8);
+        buffer.write(&[]);
+        assert!(buffer.slice().is_none());
+        buffer.write(&[1; 5]);
+        assert_eq!((buffer.count, buffer.capacity, buffer.generation()), (5, 8, 1));
+        assert_eq!(read_back::<u32>(&device, &buffer.buffer, 5), vec![1; 5]);
+    }
 }
@@ -1,5 +1,10 @@
 mod index; mod vertex;
 mod storage; mod uniform;
+mod belt; mod pool;
+mod arena; mod readback;
+
+use crate::Device;
+use std::any::TypeId;
 
 #[derive(Debug, Clone, Copy)]
 #[derive(Hash, PartialEq, Eq)]
@@ -8,6 +13,32 @@ pub enum BufferType {
     Storage, Uniform,
 }
 
+/// The runtime layout of a buffer's elements, all a `Dynamic` buffer knows about its type
+#[derive(Debug, Clone, Copy)]
+#[derive(Hash, PartialEq, Eq)]
+pub struct Element {
+    pub size: usize,
+    pub align: usize,
+    pub name: &'static str,
+    id: TypeId,
+}
+
+impl Element {
+    pub fn of<T: 'static>() -> Self {
+        return Self {
+            id: TypeId::of::<T>(),
+            size: size_of::<T>(),
+            align: align_of::<T>(),
+            name: std::any::type_name::<T>(),
+        };
+    }
+
+    pub fn is<T: 'static>(&self) -> bool {
+        return self.id == TypeId::of::<T>();
+    }
+}
+
+/// What every buffer has in common, regardless of its kind or element type
 pub trait Buffer {
     fn size(&self) -> usize;
     fn label(&self) -> String;
@@ -15,9 +46,139 @@ pub trait Buffer {
     fn r#type(&self) -> BufferType;
     fn buffer(&self) -> &wgpu::Buffer;
     fn usage(&self) -> wgpu::BufferUsages;
+    fn element(&self) -> Element;
+}
+
+/// Grows to at least this many elements, so a few pushes don't each reallocate
+const MIN_CAPACITY: usize = 4;
+
+/// Sizes and offsets handed to wgpu have to be multiples of `COPY_BUFFER_ALIGNMENT`
+pub(crate) fn aligned(size: usize) -> u64 {
+    return (size as u64).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+}
+
+/// The capacity to grow to so `required` elements fit, at least doubling so pushes are amortized
+pub(crate) fn grown_capacity(capacity: usize, required: usize) -> usize {
+    return required.max(capacity * 2).max(MIN_CAPACITY);
+}
+
+/// Never zero sized, empty buffers still get one aligned element so they can be bound
+pub(crate) fn create(device: &Device, label: Option<&str>, usage: wgpu::BufferUsages, size: usize) -> wgpu::Buffer {
+    return device.device.create_buffer(&wgpu::BufferDescriptor {
+        label: label,
+        usage: usage,
+        mapped_at_creation: false,
+        size: aligned(size).max(wgpu::COPY_BUFFER_ALIGNMENT),
+    });
+}
+
+/// A CPU copy of a buffer's contents, for writes that don't line up with `COPY_BUFFER_ALIGNMENT`
+///
+/// wgpu only copies whole 4 byte words, so those writes are widened to the words around
+/// them and the bytes they don't cover are filled in from the copy. Buffers of elements
+/// that are a multiple of 4 bytes never write anything else and don't keep one.
+#[derive(Debug, Default)]
+pub(crate) struct Shadow {
+    bytes: Option<Vec<u8>>,
+}
+
+impl Shadow {
+    /// Only keeps a copy if writes of `element` sized values can be unaligned
+    pub(crate) fn new(element: usize) -> Self {
+        return Self {
+            bytes: (!(element as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)).then(Vec::new),
+        };
+    }
+
+    /// Always keeps a copy, for buffers written at any byte offset
+    pub(crate) fn always() -> Self {
+        return Self {
+            bytes: Some(Vec::new()),
+        };
+    }
+
+    /// Forget everything, before the whole buffer is replaced
+    pub(crate) fn clear(&mut self) {
+        if let Some(bytes) = &mut self.bytes {
+            bytes.clear();
+        }
+    }
+}
+
+/// Write `bytes` at `offset`, widening the write to whole words through `shadow` if it has to be
+pub(crate) fn write(device: &Device, buffer: &wgpu::Buffer, offset: usize, bytes: &[u8], shadow: &mut Shadow) {
+    if bytes.is_empty() {
+        return;
+    }
+
+    let end = offset + bytes.len();
+    let Some(copy) = &mut shadow.bytes else {
+        debug_assert!((offset as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) && (end as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT));
+        device.queue.write_buffer(buffer, offset as u64, bytes);
+        return;
+    };
+    if copy.len() < end {
+        copy.resize(end, 0);
+    }
+    copy[offset..end].copy_from_slice(bytes);
+
+    let start = offset - offset % wgpu::COPY_BUFFER_ALIGNMENT as usize;
+    let end = aligned(end) as usize;
+    let mut window = copy[start..end.min(copy.len())].to_vec();
+    // Anything past the data is padding nothing reads, buffers are always created big enough for it
+    window.resize(end - start, 0);
+    device.queue.write_buffer(buffer, start as u64, &window);
+}
+
+/// Allocate a bigger buffer and copy the first `used` bytes of `buffer` into it
+///
+/// Anything bound to the old buffer has to be rebound, callers bump their generation.
+pub(crate) fn reallocate(device: &Device, buffer: &wgpu::Buffer, label: Option<&str>, used: usize, size: usize) -> wgpu::Buffer {
+    log::debug!("Growing buffer {:?} from {} to {} bytes", label, buffer.size(), aligned(size));
+    let grown = create(device, label, buffer.usage(), size);
+    if used != 0 {
+        let mut encoder = device.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Buffer Growth Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(buffer, 0, &grown, 0, aligned(used).min(buffer.size()));
+        device.queue.submit(Some(encoder.finish()));
+    }
+    return grown;
+}
+
+/// Any adapter at all, tests needing one skip without it
+#[cfg(test)]
+pub(crate) fn test_device() -> Option<Device> {
+    return futures_lite::future::block_on(async {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await.ok()?;
+        // Compressed formats are only tested where the adapter has them
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            required_features: adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC,
+            ..Default::default()
+        }).await.ok()?;
+        return Some(Device {
+            queue: queue,
+            device: device,
+            adapter: adapter,
+            instance: instance,
+        });
+    });
+}
+
+/// The first `count` elements of `buffer`, read back from the GPU
+#[cfg(test)]
+pub(crate) fn read_back<T: bytemuck::Pod>(device: &Device, buffer: &wgpu::Buffer, count: usize) -> Vec<T> {
+    let size = count * size_of::<T>();
+    let bytes = futures_lite::future::block_on(read_buffer(&device.device, &device.queue, buffer, size)).unwrap();
+    return bytemuck::pod_collect_to_vec(&bytes[..size]);
 }
 
-pub use index::IndexBuffer;
+pub use index::{IndexBuffer, IndexType};
+pub use belt::StagingBelt;
+pub use arena::UniformArena;
+pub use pool::{GeometryPool, GeometryAllocation};
+pub use readback::{read_buffer, ReadbackError};
 pub use vertex::VertexBuffer;
 pub use storage::StorageBuffer;
 pub use uniform::{UniformBuffer};
@@ -0,0 +1,171 @@
+use bytemuck::Pod;
+use std::ops::Range;
+use crate::Device;
+use super::{IndexBuffer, IndexType, VertexBuffer};
+
+/// Where a mesh ended up inside a `GeometryPool`
+///
+/// Ranges are in elements of the pool's buffers, indices stay relative to the
+/// mesh's own vertices and are offset with `base_vertex` when drawing.
+#[derive(Debug, Clone)]
+#[derive(Hash, PartialEq, Eq)]
+pub struct GeometryAllocation {
+    pub vertices: Range<usize>,
+    pub indices: Range<usize>,
+    /// The number of indices to draw
+    pub index_count: u32,
+    indices_per_element: u32,
+}
+
+impl GeometryAllocation {
+    /// The `base_vertex` to pass to `draw_indexed`
+    pub fn base_vertex(&self) -> i32 {
+        return self.vertices.start as i32;
+    }
+
+    /// The index range to pass to `draw_indexed`
+    pub fn draw_range(&self) -> Range<u32> {
+        let start = self.indices.start as u32 * self.indices_per_element;
+        return start..start + self.index_count;
+    }
+}
+
+/// Vertices and indices of many meshes sub-allocated from one vertex and one index buffer
+///
+/// Binding the pool's buffers once lets every mesh in it be drawn with only
+/// `draw_indexed` calls. Freed ranges are reused first fit, and the buffers only grow
+/// when nothing fits, which bumps their generation like any other buffer.
+pub struct GeometryPool<V, I> {
+    pub vertices: VertexBuffer<V>,
+    pub indices: IndexBuffer<I>,
+    // Sorted by start and never touching, neighbours are merged when freed
+    free_vertices: Vec<Range<usize>>,
+    free_indices: Vec<Range<usize>>,
+}
+
+impl<V: Pod, I: IndexType> GeometryPool<V, I> {
+    pub fn new(device: &Device, label: Option<&str>, vertex_capacity: usize, index_capacity: usize) -> Self {
+        let label = label.unwrap_or("Geometry Pool");
+        return Self {
+            free_vertices: Vec::new(),
+            free_indices: Vec::new(),
+            vertices: VertexBuffer::new(device, Some(&format!("{} Vertices", label)), vertex_capacity),
+            indices: IndexBuffer::new(device, Some(&format!("{} Indices", label)), index_capacity),
+        };
+    }
+
+    pub fn allocate(&mut self, vertices: &[V], indices: &[I]) -> GeometryAllocation {
+        let vertex_range = Self::take(&mut self.free_vertices, self.vertices.count, vertices.len());
+        let index_range = Self::take(&mut self.free_indices, self.indices.count, indices.len());
+        self.vertices.write_range(vertex_range.start, vertices);
+        self.indices.write_range(index_range.start, indices);
+
+        return GeometryAllocation {
+            vertices: vertex_range,
+            indices: index_range,
+            indices_per_element: I::INDICES as u32,
+            index_count: (indices.len() * I::INDICES) as u32,
+        };
+    }
+
+    /// Give the allocation's ranges back, the data stays until something else is written there
+    pub fn free(&mut self, allocation: GeometryAllocation) {
+        Self::release(&mut self.free_vertices, &mut self.vertices.count, allocation.vertices);
+        Self::release(&mut self.free_indices, &mut self.indices.count, allocation.indices);
+    }
+
+    /// Both buffers' generations combined, changes whenever either is reallocated
+    pub fn generation(&self) -> u64 {
+        return self.vertices.generation() + self.indices.generation();
+    }
+
+    /// The first free range that fits `length`, otherwise the end of the buffer
+    fn take(free: &mut Vec<Range<usize>>, end: usize, length: usize) -> Range<usize> {
+        let Some(index) = free.iter().position(|range| range.len() >= length) else {
+            return end..end + length;
+        };
+
+        let start = free[index].start;
+        free[index].start += length;
+        if free[index].is_empty() {
+            free.remove(index);
+        }
+        return start..start + length;
+    }
+
+    /// Merge `range` with its free neighbours, or move `end` back if nothing is used after it
+    fn release(free: &mut Vec<Range<usize>>, end: &mut usize, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let index = free.partition_point(|free| free.start < range.start);
+        free.insert(index, range);
+        if index + 1 < free.len() && free[index].end == free[index + 1].start {
+            free[index].end = free.remove(index + 1).end;
+        }
+        if index > 0 && free[index - 1].end == free[index].start {
+            free[index - 1].end = free.remove(index).end;
+        }
+        if free.last().is_some_and(|last| last.end == *end) {
+            *end = free.pop().unwrap().start;
+        }
+    }
+}
+
+impl<V, I> std::fmt::Debug for GeometryPool<V, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeometryPool")
+            .field("vertices", &self.vertices.count)
+            .field("indices", &self.indices.count)
+            .field("free_vertices", &self.free_vertices)
+            .field("free_indices", &self.free_indices)
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Pool = GeometryPool<[f32; 3], u32>;
+
+    #[test]
+    fn release_merges_neighbours() {
+        let mut free = Vec::new();
+        let mut end = 100;
+        Pool::release(&mut free, &mut end, 10..20);
+        Pool::release(&mut free, &mut end, 30..40);
+        assert_eq!(free, vec![10..20, 30..40]);
+        Pool::release(&mut free, &mut end, 20..30);
+        assert_eq!(free, vec![10..40]);
+        Pool::release(&mut free, &mut end, 0..10);
+        Pool::release(&mut free, &mut end, 50..60);
+        assert_eq!(free, vec![0..40, 50..60]);
+        assert_eq!(end, 100);
+    }
+
+    #[test]
+    fn release_at_the_end_moves_the_append_point() {
+        let mut free = vec![0..5, 10..20];
+        let mut end = 40;
+        Pool::release(&mut free, &mut end, 30..40);
+        assert_eq!((free.clone(), end), (vec![0..5, 10..20], 30));
+        Pool::release(&mut free, &mut end, 20..30);
+        assert_eq!(end, 10);
+        Pool::release(&mut free, &mut end, 5..10);
+        assert_eq!((free, end), (vec![], 0));
+    }
+
+    #[test]
+    fn take_reuses_the_first_fit() {
+        let mut free = vec![0..2, 10..20, 30..50];
+        assert_eq!(Pool::take(&mut free, 60, 5), 10..15);
+        assert_eq!(free, vec![0..2, 15..20, 30..50]);
+        assert_eq!(Pool::take(&mut free, 60, 5), 15..20);
+        assert_eq!(free, vec![0..2, 30..50]);
+        assert_eq!(Pool::take(&mut free, 60, 30), 60..90);
+        assert_eq!(Pool::take(&mut free, 60, 2), 0..2);
+        assert_eq!(free, vec![30..50]);
+    }
+}
@@ -0,0 +1,99 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum ReadbackError {
+    /// The staging buffer couldn't be mapped, usually because the device was lost
+    Map(wgpu::BufferAsyncError),
+    Poll(wgpu::PollError),
+}
+
+impl Display for ReadbackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadbackError::Map(error) => write!(f, "Failed to map the staging buffer: {}", error),
+            ReadbackError::Poll(error) => write!(f, "Failed to wait for the device: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ReadbackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return match self {
+            ReadbackError::Map(error) => Some(error),
+            ReadbackError::Poll(error) => Some(error),
+        };
+    }
+}
+
+#[derive(Default)]
+struct MapState {
+    waker: Option<Waker>,
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+}
+
+/// Resolves once the `map_async` callback has run
+struct MapFuture {
+    state: Arc<Mutex<MapState>>,
+}
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        return match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        };
+    }
+}
+
+/// Copy the first `size` bytes of `buffer` into a staging buffer and read them back
+///
+/// `buffer` needs `COPY_SRC`. On native this blocks on the device until the copy
+/// is done, on the web the future waits for the browser to map the buffer instead.
+/// Takes the raw wgpu handles so buffers created outside of gfx-ne can be read too.
+pub async fn read_buffer(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer, size: usize) -> Result<Vec<u8>, ReadbackError> {
+    let size = super::aligned(size).min(buffer.size());
+    if size == 0 {
+        return Ok(Vec::new());
+    }
+
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        size: size,
+        mapped_at_creation: false,
+        label: Some("Readback Staging Buffer"),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+    let submission = queue.submit(Some(encoder.finish()));
+
+    let state = Arc::new(Mutex::new(MapState::default()));
+    let callback_state = state.clone();
+    staging.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+        let mut state = callback_state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    device.poll(wgpu::PollType::Wait {
+        timeout: None,
+        submission_index: Some(submission),
+    }).map_err(ReadbackError::Poll)?;
+    MapFuture { state: state }.await.map_err(ReadbackError::Map)?;
+
+    let bytes = staging.slice(..).get_mapped_range().to_vec();
+    staging.unmap();
+    return Ok(bytes);
+}
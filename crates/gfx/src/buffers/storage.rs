@@ -1,14 +1,22 @@
+use bytemuck::Pod;
 use std::marker::PhantomData;
-use bytemuck::{Pod, Zeroable};
 use std::sync::atomic::{AtomicU64, Ordering};
-use crate::{Device, WgpuExt, Dynamic, Buffer};
+use super::{Buffer, BufferType, Element, ReadbackError, Shadow};
+use crate::{Device, Dynamic};
 
+/// A runtime sized `array<T>` in a storage buffer
+#[repr(C)]
 pub struct StorageBuffer<T> {
+    pub count: usize,
     pub device: Device,
+    pub capacity: usize,
     generation: AtomicU64,
     phantom: PhantomData<T>,
     pub buffer: wgpu::Buffer,
     pub label: Option<String>,
+    /// What `T` is once erased to `Dynamic`, private so `downcast` can trust it
+    element: Element,
+    shadow: Shadow,
 }
 
 impl<T> StorageBuffer<T> {
@@ -20,9 +28,100 @@ impl<T> StorageBuffer<T> {
         return self.generation.load(Ordering::Acquire);
     }
 
-    pub unsafe fn transmute<U>(self) -> StorageBuffer<U> {
-        let this = std::mem::ManuallyDrop::new(self);
-        return std::mem::transmute_copy(&*this);
+    /// Forget `T`, the element layout stays available through `element`
+    pub fn erase(self) -> StorageBuffer<Dynamic> {
+        return StorageBuffer {
+            count: self.count,
+            label: self.label,
+            device: self.device,
+            buffer: self.buffer,
+            element: self.element,
+            shadow: self.shadow,
+            capacity: self.capacity,
+            generation: self.generation,
+            phantom: PhantomData,
+        };
+    }
+}
+
+impl<T: Pod> StorageBuffer<T> {
+    pub const USAGE: wgpu::BufferUsages = wgpu::BufferUsages::STORAGE
+        .union(wgpu::BufferUsages::COPY_DST)
+    .union(wgpu::BufferUsages::COPY_SRC);
+
+    /// An empty buffer with room for `capacity` elements
+    pub fn new(device: &Device, label: Option<&str>, capacity: usize) -> Self {
+        return Self {
+            count: 0,
+            capacity: capacity,
+            device: device.clone(),
+            phantom: PhantomData,
+            generation: AtomicU64::new(0),
+            element: Element::of::<T>(),
+            shadow: Shadow::new(size_of::<T>()),
+            label: label.map(String::from),
+            buffer: super::create(device, label, Self::USAGE, capacity * size_of::<T>()),
+        };
+    }
+
+    pub fn from_slice(device: &Device, label: Option<&str>, data: &[T]) -> Self {
+        let mut buffer = Self::new(device, label, data.len());
+        buffer.write(data);
+        return buffer;
+    }
+
+    /// Replace every element, growing if `data` doesn't fit
+    pub fn write(&mut self, data: &[T]) {
+        self.count = 0;
+        self.reserve(data.len());
+        self.shadow.clear();
+        super::write(&self.device, &self.buffer, 0, bytemuck::cast_slice(data), &mut self.shadow);
+        self.count = data.len();
+    }
+
+    /// Overwrite elements starting at `offset`, anything past the end is appended
+    pub fn write_range(&mut self, offset: usize, data: &[T]) {
+        assert!(offset <= self.count, "Writing elements at {} would leave a gap after the {} in {:?}", offset, self.count, self.label);
+        self.reserve(offset + data.len());
+        super::write(&self.device, &self.buffer, offset * size_of::<T>(), bytemuck::cast_slice(data), &mut self.shadow);
+        self.count = self.count.max(offset + data.len());
+    }
+
+    pub fn push(&mut self, data: &[T]) {
+        self.write_range(self.count, data);
+    }
+
+    /// Make room for `count` elements, keeping the current ones
+    ///
+    /// Growing replaces the wgpu buffer and bumps the generation, bind groups using it have to be recreated.
+    pub fn reserve(&mut self, count: usize) {
+        if count <= self.capacity {
+            return;
+        }
+
+        let capacity = super::grown_capacity(self.capacity, count);
+        self.buffer = super::reallocate(&self.device, &self.buffer, self.label.as_deref(), self.count * size_of::<T>(), capacity * size_of::<T>());
+        self.capacity = capacity;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    pub fn as_entire_binding(&self) -> wgpu::BindingResource<'_> {
+        return self.buffer.as_entire_binding();
+    }
+
+    /// Copy the elements back from the GPU, including anything a compute shader wrote
+    pub async fn read(&self) -> Result<Vec<T>, ReadbackError> {
+        let size = self.count * size_of::<T>();
+        let bytes = super::read_buffer(&self.device.device, &self.device.queue, &self.buffer, size).await?;
+        return Ok(bytemuck::pod_collect_to_vec(&bytes[..size]));
+    }
+
+    /// `read`, blocking until the data is back
+    ///
+    /// Not on the web, the browser only maps the buffer once control goes back to it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_blocking(&self) -> Result<Vec<T>, ReadbackError> {
+        return futures_lite::future::block_on(self.read());
     }
 }
 
@@ -34,36 +133,114 @@ impl<T> AsRef<wgpu::Buffer> for StorageBuffer<T> {
 
 impl<T> AsRef<StorageBuffer<Dynamic>> for StorageBuffer<T> {
     fn as_ref(&self) -> &StorageBuffer<Dynamic> {
-        unsafe { std::mem::transmute(self) }
+        // SAFETY: the struct is `repr(C)` and `T` only appears in a `PhantomData`,
+        // so every `StorageBuffer<T>` has the same layout
+        return unsafe { &*(self as *const Self as *const StorageBuffer<Dynamic>) };
     }
 }
 
+impl StorageBuffer<Dynamic> {
+    /// Get the typed buffer back if it really holds `U`, otherwise the buffer itself, boxed
+    pub fn downcast<U: 'static>(self) -> Result<StorageBuffer<U>, Box<Self>> {
+        if !self.element.is::<U>() {
+            return Err(Box::new(self));
+        }
+        return Ok(StorageBuffer {
+            count: self.count,
+            label: self.label,
+            device: self.device,
+            buffer: self.buffer,
+            element: self.element,
+            shadow: self.shadow,
+            capacity: self.capacity,
+            generation: self.generation,
+            phantom: PhantomData,
+        });
+    }
 
-#[allow(unused)]
-fn test(device: Device) {
-    struct Lights {
-        pub value: [f32; 4]
+    pub fn downcast_ref<U: 'static>(&self) -> Option<&StorageBuffer<U>> {
+        // SAFETY: same layout as above, and the element type was checked
+        return self.element.is::<U>().then(|| unsafe { &*(self as *const Self as *const StorageBuffer<U>) });
     }
+}
 
-    // If any "attribute" is unknown it is defined at runtime
-    // If known this is a compile-time constant and can be used
+impl<T> Buffer for StorageBuffer<T> {
+    fn size(&self) -> usize {
+        return self.buffer.size() as usize;
+    }
 
-    // known type; unknown size
-    let _: StorageBuffer<f32>;
+    fn label(&self) -> String {
+        return self.label.clone().unwrap_or_default();
+    }
 
-    // unknown type; unknown size
-    let _: StorageBuffer<[u32; 4]>;
+    fn generation(&self) -> u64 {
+        return self.generation.load(Ordering::Acquire);
+    }
 
-    // known type; unknown size
-    let _: StorageBuffer<Lights>;
+    fn r#type(&self) -> BufferType {
+        return BufferType::Storage;
+    }
 
-    // unknown type; unknown size
-    let _: StorageBuffer<Dynamic>;
+    fn buffer(&self) -> &wgpu::Buffer {
+        return &self.buffer;
+    }
 
-    // known type; known size
-    let _: StorageBuffer<[Lights; 4]>;
+    fn usage(&self) -> wgpu::BufferUsages {
+        return self.buffer.usage();
+    }
 
-    // unknown type; known size
-    let _: StorageBuffer<[Dynamic; 6]>;
+    fn element(&self) -> Element {
+        return self.element;
+    }
+}
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffers::test_device as device;
+
+    #[test]
+    fn reads_back_what_was_written() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut buffer = StorageBuffer::<u32>::from_slice(&device, Some("Readback Test"), &[1, 2, 3]);
+        buffer.push(&[4, 5]);
+        buffer.write_range(1, &[20]);
+        assert_eq!(futures_lite::future::block_on(buffer.read()).unwrap(), vec![1, 20, 3, 4, 5]);
+    }
+
+    #[test]
+    fn unaligned_writes_keep_their_neighbours() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        // 6 byte elements, so every other one starts and ends halfway through a word
+        let mut buffer = StorageBuffer::<[u16; 3]>::from_slice(&device, Some("Unaligned Test"), &[[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+        buffer.write_range(1, &[[40, 50, 60]]);
+        buffer.push(&[[10, 11, 12]]);
+        let read = futures_lite::future::block_on(buffer.read()).unwrap();
+        assert_eq!(read, vec![[1, 2, 3], [40, 50, 60], [7, 8, 9], [10, 11, 12]]);
+    }
+
+    #[test]
+    fn growing_keeps_elements_and_bumps_the_generation() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut buffer = StorageBuffer::<u32>::new(&device, Some("Growth Test"), 2);
+        buffer.push(&[1, 2]);
+        assert_eq!((buffer.capacity, buffer.generation()), (2, 0));
+        buffer.push(&[3]);
+        assert_eq!((buffer.capacity, buffer.generation()), (4, 1));
+        buffer.write(&[5; 9]);
+        assert_eq!((buffer.capacity, buffer.count, buffer.generation()), (9, 9, 2));
+        buffer.write(&[6, 7]);
+        assert_eq!((buffer.capacity, buffer.count, buffer.generation()), (9, 2, 2));
+        assert_eq!(buffer.read_blocking().unwrap(), vec![6, 7]);
+    }
 }
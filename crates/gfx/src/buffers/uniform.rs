@@ -1,14 +1,21 @@
+use bytemuck::Pod;
+use std::sync::Mutex;
 use std::marker::PhantomData;
-use bytemuck::{Pod, Zeroable};
 use std::sync::atomic::{AtomicU64, Ordering};
-use crate::{Device, WgpuExt, Dynamic, Buffer};
+use super::{Buffer, BufferType, Element, Shadow};
+use crate::{Device, Dynamic};
 
+/// A single `T` in a uniform buffer, arrays are written as `UniformBuffer<[T; N]>`
+#[repr(C)]
 pub struct UniformBuffer<T> {
     pub device: Device,
     generation: AtomicU64,
     phantom: PhantomData<T>,
     pub buffer: wgpu::Buffer,
     pub label: Option<String>,
+    /// What `T` is once erased to `Dynamic`, private so `downcast` can trust it
+    element: Element,
+    shadow: Mutex<Shadow>,
 }
 
 impl<T> UniformBuffer<T> {
@@ -20,9 +27,53 @@ impl<T> UniformBuffer<T> {
         return self.generation.load(Ordering::Acquire);
     }
 
-    pub unsafe fn transmute<U>(self) -> UniformBuffer<U> {
-        let this = std::mem::ManuallyDrop::new(self);
-        return std::mem::transmute_copy(&*this);
+    /// Forget `T`, the element layout stays available through `element`
+    pub fn erase(self) -> UniformBuffer<Dynamic> {
+        return UniformBuffer {
+            label: self.label,
+            device: self.device,
+            buffer: self.buffer,
+            element: self.element,
+            shadow: self.shadow,
+            generation: self.generation,
+            phantom: PhantomData,
+        };
+    }
+}
+
+impl<T: Pod> UniformBuffer<T> {
+    pub const USAGE: wgpu::BufferUsages = wgpu::BufferUsages::UNIFORM
+        .union(wgpu::BufferUsages::COPY_DST)
+    .union(wgpu::BufferUsages::COPY_SRC);
+
+    pub fn new(device: &Device, label: Option<&str>, value: &T) -> Self {
+        let buffer = Self {
+            device: device.clone(),
+            phantom: PhantomData,
+            generation: AtomicU64::new(0),
+            element: Element::of::<T>(),
+            // `write_range` can start anywhere
+            shadow: Mutex::new(Shadow::always()),
+            label: label.map(String::from),
+            buffer: super::create(device, label, Self::USAGE, size_of::<T>()),
+        };
+        buffer.write(value);
+        return buffer;
+    }
+
+    /// Uniforms have a fixed size, so unlike the other buffers this never reallocates
+    pub fn write(&self, value: &T) {
+        super::write(&self.device, &self.buffer, 0, bytemuck::bytes_of(value), &mut self.shadow.lock().unwrap());
+    }
+
+    /// Overwrite `bytes` of the uniform starting at `offset`, e.g. a single field found with `offset_of!`
+    pub fn write_range(&self, offset: usize, bytes: &[u8]) {
+        assert!(offset + bytes.len() <= size_of::<T>(), "Writing {} bytes at {} overflows {:?}", bytes.len(), offset, self.label);
+        super::write(&self.device, &self.buffer, offset, bytes, &mut self.shadow.lock().unwrap());
+    }
+
+    pub fn as_entire_binding(&self) -> wgpu::BindingResource<'_> {
+        return self.buffer.as_entire_binding();
     }
 }
 
@@ -34,36 +85,108 @@ impl<T> AsRef<wgpu::Buffer> for UniformBuffer<T> {
 
 impl<T> AsRef<UniformBuffer<Dynamic>> for UniformBuffer<T> {
     fn as_ref(&self) -> &UniformBuffer<Dynamic> {
-        unsafe { std::mem::transmute(self) }
+        // SAFETY: the struct is `repr(C)` and `T` only appears in a `PhantomData`,
+        // so every `UniformBuffer<T>` has the same layout
+        return unsafe { &*(self as *const Self as *const UniformBuffer<Dynamic>) };
     }
 }
 
+impl UniformBuffer<Dynamic> {
+    /// Get the typed buffer back if it really holds `U`, otherwise the buffer itself, boxed
+    pub fn downcast<U: 'static>(self) -> Result<UniformBuffer<U>, Box<Self>> {
+        if !self.element.is::<U>() {
+            return Err(Box::new(self));
+        }
+        return Ok(UniformBuffer {
+            label: self.label,
+            device: self.device,
+            buffer: self.buffer,
+            element: self.element,
+            shadow: self.shadow,
+            generation: self.generation,
+            phantom: PhantomData,
+        });
+    }
 
-#[allow(unused)]
-fn test(device: Device) {
-    struct Position {
-        pub value: [f32; 4]
+    pub fn downcast_ref<U: 'static>(&self) -> Option<&UniformBuffer<U>> {
+        // SAFETY: same layout as above, and the element type was checked
+        return self.element.is::<U>().then(|| unsafe { &*(self as *const Self as *const UniformBuffer<U>) });
     }
+}
 
-    // If any "attribute" is unknown it is defined at runtime
-    // If known this is a compile-time constant and can be used
+impl<T> Buffer for UniformBuffer<T> {
+    fn size(&self) -> usize {
+        return self.buffer.size() as usize;
+    }
 
-    // known type; unknown size
-    let _: UniformBuffer<u32>;
+    fn label(&self) -> String {
+        return self.label.clone().unwrap_or_default();
+    }
 
-    // unknown type; unknown size
-    let _: UniformBuffer<Dynamic>;
+    fn generation(&self) -> u64 {
+        return self.generation.load(Ordering::Acquire);
+    }
 
-    // known type; known size
-    let _: UniformBuffer<[f32; 4]>;
+    fn r#type(&self) -> BufferType {
+        return BufferType::Uniform;
+    }
 
-    // known type; unknown size
-    let _: UniformBuffer<Position>;
+    fn buffer(&self) -> &wgpu::Buffer {
+        return &self.buffer;
+    }
 
-    // unknown type; known size
-    let _: UniformBuffer<[Dynamic; 6]>;
+    fn usage(&self) -> wgpu::BufferUsages {
+        return self.buffer.usage();
+    }
 
-    // known type; known size
-    let _: UniformBuffer<[Position; 3]>;
+    fn element(&self) -> Element {
+        return self.element;
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+    use crate::buffers::{read_back, test_device as device};
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    #[derive(PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Material {
+        color: [f32; 3],
+        flags: [u8; 2],
+        roughness: u16,
+    }
+
+    #[test]
+    fn write_range_updates_one_field() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let material = Material { color: [1.0, 0.5, 0.25], flags: [1, 2], roughness: 3 };
+        let buffer = UniformBuffer::new(&device, Some("Uniform Range Test"), &material);
+        // Two bytes in the middle of a word, the rest of it comes from the shadow copy
+        buffer.write_range(offset_of!(Material, flags) + 1, &[20]);
+        buffer.write_range(offset_of!(Material, roughness), bytemuck::bytes_of(&30u16));
+        let expected = Material { flags: [1, 20], roughness: 30, ..material };
+        assert_eq!(read_back::<Material>(&device, &buffer.buffer, 1), vec![expected]);
+
+        buffer.write(&Material { roughness: 4, ..material });
+        assert_eq!(read_back::<Material>(&device, &buffer.buffer, 1)[0].roughness, 4);
+        // Uniforms never grow
+        assert_eq!((buffer.size(), buffer.generation()), (16, 0));
+    }
+
+    #[test]
+    fn write_range_past_the_end() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let buffer = UniformBuffer::new(&device, None, &[0u32; 2]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| buffer.write_range(6, &[0; 4])));
+        assert!(result.is_err());
+    }
+}
@@ -1,8 +1,10 @@
+use bytemuck::Pod;
 use std::marker::PhantomData;
-use bytemuck::{Pod, Zeroable};
 use std::sync::atomic::{Ordering, AtomicU64};
-use crate::{Device, WgpuExt, Dynamic, Buffer};
+use super::{Buffer, BufferType, Element, Shadow};
+use crate::{Device, Dynamic};
 
+#[repr(C)]
 pub struct VertexBuffer<T> {
     pub count: usize,
     pub stride: usize,
@@ -12,6 +14,9 @@ pub struct VertexBuffer<T> {
     phantom: PhantomData<T>,
     pub buffer: wgpu::Buffer,
     pub label: Option<String>,
+    /// What `T` is once erased to `Dynamic`, private so `downcast` can trust it
+    element: Element,
+    shadow: Shadow,
 }
 
 impl<T> VertexBuffer<T> {
@@ -23,9 +28,91 @@ impl<T> VertexBuffer<T> {
         return self.generation.load(Ordering::Acquire);
     }
 
-    pub unsafe fn transmute<U>(self) -> VertexBuffer<U> {
-        let this = std::mem::ManuallyDrop::new(self);
-        return std::mem::transmute_copy(&*this);
+    /// Forget `T`, the element layout stays available through `element`
+    pub fn erase(self) -> VertexBuffer<Dynamic> {
+        return VertexBuffer {
+            count: self.count,
+            label: self.label,
+            stride: self.stride,
+            device: self.device,
+            buffer: self.buffer,
+            element: self.element,
+            shadow: self.shadow,
+            capacity: self.capacity,
+            generation: self.generation,
+            phantom: PhantomData,
+        };
+    }
+}
+
+impl<T: Pod> VertexBuffer<T> {
+    pub const USAGE: wgpu::BufferUsages = wgpu::BufferUsages::VERTEX
+        .union(wgpu::BufferUsages::COPY_DST)
+    .union(wgpu::BufferUsages::COPY_SRC);
+
+    /// An empty buffer with room for `capacity` vertices
+    pub fn new(device: &Device, label: Option<&str>, capacity: usize) -> Self {
+        return Self {
+            count: 0,
+            capacity: capacity,
+            device: device.clone(),
+            phantom: PhantomData,
+            stride: size_of::<T>(),
+            generation: AtomicU64::new(0),
+            element: Element::of::<T>(),
+            shadow: Shadow::new(size_of::<T>()),
+            label: label.map(String::from),
+            buffer: super::create(device, label, Self::USAGE, capacity * size_of::<T>()),
+        };
+    }
+
+    pub fn from_slice(device: &Device, label: Option<&str>, data: &[T]) -> Self {
+        let mut buffer = Self::new(device, label, data.len());
+        buffer.write(data);
+        return buffer;
+    }
+
+    /// Replace every vertex, growing if `data` doesn't fit
+    pub fn write(&mut self, data: &[T]) {
+        // Nothing worth copying over if it has to grow
+        self.count = 0;
+        self.reserve(data.len());
+        self.shadow.clear();
+        super::write(&self.device, &self.buffer, 0, bytemuck::cast_slice(data), &mut self.shadow);
+        self.count = data.len();
+    }
+
+    /// Overwrite vertices starting at `offset`, anything past the end is appended
+    pub fn write_range(&mut self, offset: usize, data: &[T]) {
+        assert!(offset <= self.count, "Writing vertices at {} would leave a gap after the {} in {:?}", offset, self.count, self.label);
+        self.reserve(offset + data.len());
+        super::write(&self.device, &self.buffer, offset * self.stride, bytemuck::cast_slice(data), &mut self.shadow);
+        self.count = self.count.max(offset + data.len());
+    }
+
+    pub fn push(&mut self, data: &[T]) {
+        self.write_range(self.count, data);
+    }
+
+    /// Make room for `count` vertices, keeping the current ones
+    ///
+    /// Growing replaces the wgpu buffer and bumps the generation.
+    pub fn reserve(&mut self, count: usize) {
+        if count <= self.capacity {
+            return;
+        }
+
+        let capacity = super::grown_capacity(self.capacity, count);
+        self.buffer = super::reallocate(&self.device, &self.buffer, self.label.as_deref(), self.count * self.stride, capacity * self.stride);
+        self.capacity = capacity;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// The part of the buffer holding vertices, for `set_vertex_buffer`
+    ///
+    /// wgpu can't slice nothing, so this is `None` while the buffer is empty.
+    pub fn slice(&self) -> Option<wgpu::BufferSlice<'_>> {
+        return (self.count != 0).then(|| self.buffer.slice(..(self.count * self.stride) as u64));
     }
 }
 
@@ -37,29 +124,113 @@ impl<T> AsRef<wgpu::Buffer> for VertexBuffer<T> {
 
 impl<T> AsRef<VertexBuffer<Dynamic>> for VertexBuffer<T> {
     fn as_ref(&self) -> &VertexBuffer<Dynamic> {
-        unsafe { std::mem::transmute(self) }
+        // SAFETY: the struct is `repr(C)` and `T` only appears in a `PhantomData`,
+        // so every `VertexBuffer<T>` has the same layout
+        return unsafe { &*(self as *const Self as *const VertexBuffer<Dynamic>) };
     }
 }
 
+impl VertexBuffer<Dynamic> {
+    /// Get the typed buffer back if it really holds `U`, otherwise the buffer itself, boxed
+    pub fn downcast<U: 'static>(self) -> Result<VertexBuffer<U>, Box<Self>> {
+        if !self.element.is::<U>() {
+            return Err(Box::new(self));
+        }
+        return Ok(VertexBuffer {
+            count: self.count,
+            label: self.label,
+            stride: self.stride,
+            device: self.device,
+            buffer: self.buffer,
+            element: self.element,
+            shadow: self.shadow,
+            capacity: self.capacity,
+            generation: self.generation,
+            phantom: PhantomData,
+        });
+    }
 
-#[allow(unused)]
-fn test(device: Device) {
-    struct Vertex {
-        pub position: [u32; 3]
+    pub fn downcast_ref<U: 'static>(&self) -> Option<&VertexBuffer<U>> {
+        // SAFETY: same layout as above, and the element type was checked
+        return self.element.is::<U>().then(|| unsafe { &*(self as *const Self as *const VertexBuffer<U>) });
     }
+}
 
-    // If any "attribute" is unknown it is defined at runtime
-    // If known this is a compile-time constant and can be used
+impl<T> Buffer for VertexBuffer<T> {
+    fn size(&self) -> usize {
+        return self.buffer.size() as usize;
+    }
+
+    fn label(&self) -> String {
+        return self.label.clone().unwrap_or_default();
+    }
+
+    fn generation(&self) -> u64 {
+        return self.generation.load(Ordering::Acquire);
+    }
 
-    // known type; unknown size
-    let _: VertexBuffer<Vertex>;
+    fn r#type(&self) -> BufferType {
+        return BufferType::Vertex;
+    }
 
-    // unknown type; unknown size
-    let _: VertexBuffer<Dynamic>;
+    fn buffer(&self) -> &wgpu::Buffer {
+        return &self.buffer;
+    }
 
-    // known type; known size
-    let _: VertexBuffer<[Vertex; 3]>;
+    fn usage(&self) -> wgpu::BufferUsages {
+        return self.buffer.usage();
+    }
 
-    // known type; unknown size
-    let _: VertexBuffer<[Dynamic; 6]>;
+    fn element(&self) -> Element {
+        return self.element;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffers::{read_back, test_device as device};
+
+    #[test]
+    fn push_grows_and_bumps_the_generation() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut buffer = VertexBuffer::<[f32; 3]>::new(&device, Some("Vertex Growth Test"), 2);
+        assert!(buffer.slice().is_none());
+        buffer.push(&[[0.0; 3], [1.0; 3]]);
+        assert_eq!((buffer.count, buffer.capacity, buffer.generation()), (2, 2, 0));
+        buffer.push(&[[2.0; 3]]);
+        // At least doubled, so the next push doesn't reallocate again
+        assert_eq!((buffer.count, buffer.capacity, buffer.generation()), (3, 4, 1));
+        buffer.push(&[[3.0; 3]]);
+        assert_eq!((buffer.capacity, buffer.generation()), (4, 1));
+        assert_eq!(read_back::<[f32; 3]>(&device, &buffer.buffer, 4), vec![[0.0; 3], [1.0; 3], [2.0; 3], [3.0; 3]]);
+        assert_eq!(buffer.slice().unwrap().size().get(), 48);
+    }
+
+    #[test]
+    fn write_range_overwrites_and_appends() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut buffer = VertexBuffer::<[f32; 2]>::from_slice(&device, Some("Vertex Range Test"), &[[0.0; 2], [1.0; 2], [2.0; 2]]);
+        buffer.write_range(1, &[[10.0; 2]]);
+        buffer.write_range(2, &[[20.0; 2], [30.0; 2]]);
+        assert_eq!((buffer.count, buffer.generation()), (4, 1));
+        assert_eq!(read_back::<[f32; 2]>(&device, &buffer.buffer, 4), vec![[0.0; 2], [10.0; 2], [20.0; 2], [30.0; 2]]);
+    }
+
+    #[test]
+    fn write_range_past_the_end() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let mut buffer = VertexBuffer::<[f32; 2]>::new(&device, None, 4);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| buffer.write_range(1, &[[0.0; 2]])));
+        assert!(result.is_err());
+    }
 }
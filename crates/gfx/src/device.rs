@@ -1,4 +1,5 @@
-#[derive(Debug)]
+/// Cheap to clone, every wgpu handle inside is reference counted
+#[derive(Debug, Clone)]
 pub struct Device {
     pub queue: wgpu::Queue,
     pub device: wgpu::Device,
@@ -68,3 +69,34 @@ impl Device {
         };
     }
 }
+
+/// Raw wgpu object creation, kept behind a trait so wrappers don't have to reach into the device
+pub trait WgpuExt {
+    fn wgpu_create_shader_module(&self, desc: wgpu::ShaderModuleDescriptor) -> wgpu::ShaderModule;
+    fn wgpu_create_sampler(&self, desc: &wgpu::SamplerDescriptor) -> wgpu::Sampler;
+    fn wgpu_create_texture(&self, desc: &wgpu::TextureDescriptor) -> wgpu::Texture;
+    fn wgpu_create_bind_group_layout(&self, desc: &wgpu::BindGroupLayoutDescriptor) -> wgpu::BindGroupLayout;
+    fn wgpu_create_pipeline_layout(&self, desc: &wgpu::PipelineLayoutDescriptor) -> wgpu::PipelineLayout;
+}
+
+impl WgpuExt for Device {
+    fn wgpu_create_shader_module(&self, desc: wgpu::ShaderModuleDescriptor) -> wgpu::ShaderModule {
+        return self.device.create_shader_module(desc);
+    }
+
+    fn wgpu_create_sampler(&self, desc: &wgpu::SamplerDescriptor) -> wgpu::Sampler {
+        return self.device.create_sampler(desc);
+    }
+
+    fn wgpu_create_texture(&self, desc: &wgpu::TextureDescriptor) -> wgpu::Texture {
+        return self.device.create_texture(desc);
+    }
+
+    fn wgpu_create_bind_group_layout(&self, desc: &wgpu::BindGroupLayoutDescriptor) -> wgpu::BindGroupLayout {
+        return self.device.create_bind_group_layout(desc);
+    }
+
+    fn wgpu_create_pipeline_layout(&self, desc: &wgpu::PipelineLayoutDescriptor) -> wgpu::PipelineLayout {
+        return self.device.create_pipeline_layout(desc);
+    }
+}
@@ -0,0 +1,88 @@
+/// Everything bound in one bind group, implemented with `#[derive(BindGroup)]`
+pub trait BindGroupState: Sized {
+    /// The name of the bind group
+    fn get_name(&self) -> &'static str;
+    /// Default initialization of the state
+    fn init(device: &wgpu::Device) -> Self;
+    /// Upload all of the bound buffers that changed, they reach the GPU when `uploads` is flushed
+    fn update(&mut self, uploads: &mut crate::StagingBelt);
+    /// The layout which describes how data is bound in the bind group
+    fn get_layout_descriptor(&self) -> wgpu::BindGroupLayoutDescriptor<'_>;
+    /// Create a new bind group adhering to the layout defined in `get_layout_descriptor`
+    fn create_bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup;
+    /// The offsets to bind `slot` of every dynamic binding with, in binding order
+    fn dynamic_offsets(&self, _slot: usize) -> Vec<u32> {
+        return Vec::new();
+    }
+    /// Changes whenever a bound buffer is reallocated and the bind group has to be recreated
+    fn generation(&self) -> u64 {
+        return 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StorageBuffer, UniformArena};
+    use crate::buffers::test_device as device;
+
+    #[derive(crate::BindGroup)]
+    #[bind_group(label = "Particles")]
+    struct ParticleBindGroup {
+        #[storage]
+        particles: StorageBuffer<[f32; 4]>,
+        #[storage(read_only)]
+        forces: StorageBuffer<[f32; 4]>,
+        #[storage(visibility(compute))]
+        counts: StorageBuffer<u32>,
+        #[uniform(5, dynamic)]
+        emitters: UniformArena<[f32; 4]>,
+    }
+
+    fn particles(device: &crate::Device) -> ParticleBindGroup {
+        return ParticleBindGroup {
+            particles: StorageBuffer::new(device, None, 4),
+            forces: StorageBuffer::new(device, None, 4),
+            counts: StorageBuffer::new(device, None, 1),
+            emitters: UniformArena::new(&device.device, None),
+        };
+    }
+
+    #[test]
+    fn storage_visibility_defaults() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let group = particles(&device);
+        let descriptor = group.get_layout_descriptor();
+        let entries = descriptor.entries.iter().map(|entry| (entry.binding, entry.visibility)).collect::<Vec<_>>();
+        assert_eq!(entries, vec![
+            (0, wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE),
+            (1, wgpu::ShaderStages::VERTEX_FRAGMENT),
+            (2, wgpu::ShaderStages::COMPUTE),
+            (5, wgpu::ShaderStages::VERTEX_FRAGMENT),
+        ]);
+        assert!(matches!(descriptor.entries[3].ty, wgpu::BindingType::Buffer { has_dynamic_offset: true, min_binding_size: Some(size), .. } if size.get() == 16));
+
+        // wgpu rejects writable storage visible to vertex shaders without a feature
+        device.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let layout = device.device.create_bind_group_layout(&descriptor);
+        group.create_bind_group(&device.device, &layout);
+        assert!(futures_lite::future::block_on(device.device.pop_error_scope()).is_none());
+    }
+
+    #[test]
+    fn init_needs_every_binding_to_be_a_uniform() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        let Err(panic) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| ParticleBindGroup::init(&device.device))) else {
+            panic!("init made a storage buffer out of nothing");
+        };
+        // Formatting only literals gives a `&str` rather than a `String`
+        let message = panic.downcast_ref::<String>().map(String::as_str).or(panic.downcast_ref::<&str>().copied());
+        assert_eq!(message, Some("Particles requires a pre init state!"));
+    }
+}
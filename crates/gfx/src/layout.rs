@@ -0,0 +1,262 @@
+use std::fmt::Debug;
+use bytemuck::Pod;
+
+pub use gfx_preprocessor::{MemoryLayout, StructLayout};
+
+/// A type with a GPU representation, usually `#[derive(GpuLayout)]`d
+///
+/// The representations are plain `Pod` structs with every padding byte spelled out,
+/// so they can be written straight into a buffer. Array elements have their own
+/// representations, padded up to the array's stride.
+pub trait GpuLayout {
+    type Std140: Pod + Debug;
+    type Std430: Pod + Debug;
+    type Std140Element: Pod + Debug;
+    type Std430Element: Pod + Debug;
+    const STD140_ALIGN: usize;
+    const STD430_ALIGN: usize;
+
+    fn std140(&self) -> Self::Std140;
+    fn std430(&self) -> Self::Std430;
+
+    fn std140_element(&self) -> Self::Std140Element {
+        return padded(self.std140());
+    }
+
+    fn std430_element(&self) -> Self::Std430Element {
+        return padded(self.std430());
+    }
+
+    /// The type as written in WGSL, `vec3<f32>` or a struct's name
+    fn wgsl_type(layout: MemoryLayout) -> String;
+
+    /// The type of an array element, uniform arrays widen anything smaller than 16 bytes to a `vec4`
+    fn wgsl_element(layout: MemoryLayout) -> String {
+        return Self::wgsl_type(layout);
+    }
+
+    /// The WGSL declaration of a struct, with `@align` and `@size` wherever `layout` needs them
+    ///
+    /// Empty for anything that isn't a struct. Structs nested inside have to be
+    /// declared separately, with the same layout.
+    fn wgsl_struct(_layout: MemoryLayout) -> String {
+        return String::new();
+    }
+}
+
+/// `value` followed by zeroes
+fn padded<T: Pod, U: Pod>(value: T) -> U {
+    let mut padded = U::zeroed();
+    bytemuck::bytes_of_mut(&mut padded)[..size_of::<T>()].copy_from_slice(bytemuck::bytes_of(&value));
+    return padded;
+}
+
+impl<T: GpuLayout, const N: usize> GpuLayout for [T; N] {
+    type Std140 = [T::Std140Element; N];
+    type Std430 = [T::Std430Element; N];
+    // Already a multiple of the element's stride
+    type Std140Element = Self::Std140;
+    type Std430Element = Self::Std430;
+    const STD140_ALIGN: usize = T::STD140_ALIGN.next_multiple_of(16);
+    const STD430_ALIGN: usize = T::STD430_ALIGN;
+
+    fn std140(&self) -> Self::Std140 {
+        return std::array::from_fn(|i| self[i].std140_element());
+    }
+
+    fn std430(&self) -> Self::Std430 {
+        return std::array::from_fn(|i| self[i].std430_element());
+    }
+
+    fn wgsl_type(layout: MemoryLayout) -> String {
+        return format!("array<{}, {}>", T::wgsl_element(layout), N);
+    }
+}
+
+macro_rules! builtin_layouts {
+    ($($ty:ty => $wgsl:literal ($widened:literal), $align:literal, $repr:ty, [$std140:ty, $std430:ty], |$value:ident| $convert:expr;)*) => {
+        $(impl GpuLayout for $ty {
+            type Std140 = $repr;
+            type Std430 = $repr;
+            type Std140Element = $std140;
+            type Std430Element = $std430;
+            const STD140_ALIGN: usize = $align;
+            const STD430_ALIGN: usize = $align;
+
+            fn std140(&self) -> Self::Std140 {
+                let $value = self;
+                return $convert;
+            }
+
+            fn std430(&self) -> Self::Std430 {
+                let $value = self;
+                return $convert;
+            }
+
+            fn wgsl_type(_layout: MemoryLayout) -> String {
+                return $wgsl.to_string();
+            }
+
+            fn wgsl_element(layout: MemoryLayout) -> String {
+                return match layout {
+                    MemoryLayout::Std140 => $widened.to_string(),
+                    MemoryLayout::Std430 => $wgsl.to_string(),
+                };
+            }
+        })*
+    };
+}
+
+builtin_layouts!(
+    f32 => "f32" ("vec4<f32>"), 4, f32, [[f32; 4], f32], |value| *value;
+    u32 => "u32" ("vec4<u32>"), 4, u32, [[u32; 4], u32], |value| *value;
+    i32 => "i32" ("vec4<i32>"), 4, i32, [[i32; 4], i32], |value| *value;
+
+    glam::Vec2 => "vec2<f32>" ("vec4<f32>"), 8, [f32; 2], [[f32; 4], [f32; 2]], |value| value.to_array();
+    glam::UVec2 => "vec2<u32>" ("vec4<u32>"), 8, [u32; 2], [[u32; 4], [u32; 2]], |value| value.to_array();
+    glam::IVec2 => "vec2<i32>" ("vec4<i32>"), 8, [i32; 2], [[i32; 4], [i32; 2]], |value| value.to_array();
+    glam::Vec3 => "vec3<f32>" ("vec3<f32>"), 16, [f32; 3], [[f32; 4], [f32; 4]], |value| value.to_array();
+    glam::UVec3 => "vec3<u32>" ("vec3<u32>"), 16, [u32; 3], [[u32; 4], [u32; 4]], |value| value.to_array();
+    glam::IVec3 => "vec3<i32>" ("vec3<i32>"), 16, [i32; 3], [[i32; 4], [i32; 4]], |value| value.to_array();
+    glam::Vec4 => "vec4<f32>" ("vec4<f32>"), 16, [f32; 4], [[f32; 4], [f32; 4]], |value| value.to_array();
+    glam::UVec4 => "vec4<u32>" ("vec4<u32>"), 16, [u32; 4], [[u32; 4], [u32; 4]], |value| value.to_array();
+    glam::IVec4 => "vec4<i32>" ("vec4<i32>"), 16, [i32; 4], [[i32; 4], [i32; 4]], |value| value.to_array();
+    glam::Quat => "vec4<f32>" ("vec4<f32>"), 16, [f32; 4], [[f32; 4], [f32; 4]], |value| value.to_array();
+
+    glam::Mat2 => "mat2x2<f32>" ("mat2x2<f32>"), 8, [[f32; 2]; 2], [[[f32; 2]; 2], [[f32; 2]; 2]], |value| value.to_cols_array_2d();
+    glam::Mat4 => "mat4x4<f32>" ("mat4x4<f32>"), 16, [[f32; 4]; 4], [[[f32; 4]; 4], [[f32; 4]; 4]], |value| value.to_cols_array_2d();
+    // Every column of a `mat3x3` is a `vec3` and so padded to 16 bytes
+    glam::Mat3 => "mat3x3<f32>" ("mat3x3<f32>"), 16, [[f32; 4]; 3], [[[f32; 4]; 3], [[f32; 4]; 3]], |value| {
+        value.to_cols_array_2d().map(|[x, y, z]| [x, y, z, 0.0])
+    };
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Vec2, Vec3};
+    use std::mem::offset_of;
+    use gfx_macros::GpuLayout;
+
+    /// Member offsets and size of `name` as naga lays out the WGSL declarations
+    fn naga_layout(wgsl: &str, name: &str) -> (Vec<u32>, u32) {
+        let module = naga::front::wgsl::parse_str(wgsl).unwrap_or_else(|error| panic!("{}\n{}", error, wgsl));
+        let ty = module.types.iter().find(|(_, ty)| ty.name.as_deref() == Some(name)).unwrap().1;
+        let naga::TypeInner::Struct { members, span } = &ty.inner else {
+            panic!("{} is not a struct", name);
+        };
+        return (members.iter().map(|member| member.offset).collect(), *span);
+    }
+
+    /// The same as `naga_layout`, after checking naga accepts `name` as a uniform
+    fn naga_uniform_layout(wgsl: &str, name: &str) -> (Vec<u32>, u32) {
+        let wgsl = format!("{}\n@group(0) @binding(0) var<uniform> u: {};\n", wgsl, name);
+        let module = naga::front::wgsl::parse_str(&wgsl).unwrap_or_else(|error| panic!("{}\n{}", error, wgsl));
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+        .unwrap_or_else(|error| panic!("{:?}\n{}", error, wgsl));
+        return naga_layout(&wgsl, name);
+    }
+
+    #[derive(GpuLayout)]
+    struct Light {
+        color: Vec3,
+        intensity: f32,
+        position: Vec3,
+    }
+
+    #[derive(GpuLayout)]
+    struct Inner {
+        offset: Vec2,
+    }
+
+    #[derive(GpuLayout)]
+    struct Outer {
+        scale: f32,
+        inner: Inner,
+        after: f32,
+    }
+
+    #[derive(GpuLayout)]
+    struct Material {
+        normal: glam::Mat3,
+        tint: Vec3,
+        offsets: [Vec2; 2],
+        roughness: f32,
+        light: Light,
+        flags: u32,
+    }
+
+    #[derive(GpuLayout)]
+    struct Weights {
+        count: u32,
+        weights: [f32; 3],
+    }
+
+    #[test]
+    fn scalar_packs_after_vec3() {
+        assert_eq!((offset_of!(LightStd430, intensity), offset_of!(LightStd430, position)), (12, 16));
+        assert_eq!((offset_of!(LightStd140, intensity), offset_of!(LightStd140, position)), (12, 16));
+        assert_eq!((size_of::<LightStd430>(), size_of::<LightStd140>()), (32, 32));
+        assert_eq!(naga_layout(&Light::wgsl_struct(MemoryLayout::Std430), "Light"), (vec![0, 12, 16], 32));
+        assert_eq!(naga_layout(&Light::wgsl_struct(MemoryLayout::Std140), "Light"), (vec![0, 12, 16], 32));
+    }
+
+    #[test]
+    fn nested_structs() {
+        assert_eq!((offset_of!(OuterStd430, inner), offset_of!(OuterStd430, after), size_of::<OuterStd430>()), (8, 16, 24));
+        // Uniform structs start on 16 bytes and take up a multiple of 16
+        assert_eq!((offset_of!(OuterStd140, inner), offset_of!(OuterStd140, after), size_of::<OuterStd140>()), (16, 32, 48));
+        for (layout, offsets, size) in [(MemoryLayout::Std430, vec![0, 8, 16], 24), (MemoryLayout::Std140, vec![0, 16, 32], 48)] {
+            let wgsl = Inner::wgsl_struct(layout) + &Outer::wgsl_struct(layout);
+            assert_eq!(naga_layout(&wgsl, "Outer"), (offsets, size), "{}", wgsl);
+        }
+    }
+
+    #[test]
+    fn std140_matches_naga() {
+        let offsets = [
+            offset_of!(MaterialStd140, normal),
+            offset_of!(MaterialStd140, tint),
+            offset_of!(MaterialStd140, offsets),
+            offset_of!(MaterialStd140, roughness),
+            offset_of!(MaterialStd140, light),
+            offset_of!(MaterialStd140, flags),
+        ].map(|offset| offset as u32);
+        assert_eq!(offsets, [0, 48, 64, 96, 112, 144]);
+        let wgsl = Light::wgsl_struct(MemoryLayout::Std140) + &Material::wgsl_struct(MemoryLayout::Std140);
+        assert_eq!(naga_uniform_layout(&wgsl, "Material"), (offsets.to_vec(), size_of::<MaterialStd140>() as u32));
+        assert_eq!(size_of::<MaterialStd140>(), 160);
+        assert_eq!(naga_uniform_layout(&Light::wgsl_struct(MemoryLayout::Std140), "Light"), (vec![0, 12, 16], 32));
+
+        let material = Material {
+            normal: glam::Mat3::IDENTITY,
+            tint: Vec3::ONE,
+            offsets: [Vec2::X, Vec2::Y],
+            roughness: 0.5,
+            light: Light { color: Vec3::ONE, intensity: 2.0, position: Vec3::Z },
+            flags: 3,
+        }.std140();
+        assert_eq!(material.normal, [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0]]);
+        // Every array element starts on 16 bytes, the rest is padding
+        assert_eq!(material.offsets, [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0]]);
+        assert_eq!((material.roughness, material.light.intensity, material.flags), (0.5, 2.0, 3));
+    }
+
+    #[test]
+    fn scalar_arrays() {
+        assert_eq!((offset_of!(WeightsStd430, weights), size_of::<WeightsStd430>()), (4, 16));
+        // Every element of a uniform array is widened to 16 bytes
+        assert_eq!((offset_of!(WeightsStd140, weights), size_of::<WeightsStd140>()), (16, 64));
+        let weights = Weights { count: 3, weights: [1.0, 2.0, 3.0] };
+        assert_eq!(weights.std140().weights, [[1.0, 0.0, 0.0, 0.0], [2.0, 0.0, 0.0, 0.0], [3.0, 0.0, 0.0, 0.0]]);
+        assert_eq!(weights.std430().weights, [1.0, 2.0, 3.0]);
+
+        let std430 = Weights::wgsl_struct(MemoryLayout::Std430);
+        assert!(std430.contains("weights: array<f32, 3>"), "{}", std430);
+        assert_eq!(naga_layout(&std430, "Weights"), (vec![0, 4], 16));
+        let std140 = Weights::wgsl_struct(MemoryLayout::Std140);
+        assert!(std140.contains("weights: array<vec4<f32>, 3>"), "{}", std140);
+        assert_eq!(naga_layout(&std140, "Weights"), (vec![0, 16], 64));
+    }
+}
@@ -4,18 +4,41 @@
 // TODO: instead of this lib handling that might be better for rend to rexport the gfx objects wrapped in a arc?
 
 mod device;
+mod buffers;
+mod group;
+mod layout;
+mod vertex;
 mod shader;
 mod sampler;
 mod texture;
 
+// Derived code names everything through `::gfx_ne`, including the derives in this crate's tests
+#[cfg(test)]
+extern crate self as gfx_ne;
+
 pub use buffers::*;
 
 #[derive(Debug, Clone, Copy)]
 #[derive(Hash, PartialEq, Eq)]
-/// A stub marker for dynamic types
+/// Stands in for an element type only known at runtime, e.g. `VertexBuffer<Dynamic>`
+///
+/// Typed buffers are erased with `erase` or viewed through `AsRef`, and `downcast` gets them back.
 pub struct Dynamic;
 
-pub use device::Device;
-pub use shader::Shader;
-pub use sampler::Sampler;
-pub use texture::Texture;
+pub use device::{Device, WgpuExt};
+pub use group::BindGroupState;
+pub use gfx_macros::{BindGroup, GpuLayout, VertexLayout};
+pub use layout::{GpuLayout, MemoryLayout, StructLayout};
+pub use vertex::{VertexArrayObject, VertexAttribute, Snorm, Unorm};
+pub use shader::{Shader, ShaderConstants, ShaderDefines, ShaderError, ShaderWatcher};
+pub use shader::{Preprocessor, Preprocessed, ShaderRegistry};
+pub use shader::{Diagnostic, SourceLocation, SourceMap};
+pub use shader::{ShaderStage, Reflection, EntryPoint, Override, Resource, Varying};
+pub use shader::{create_module, translate, Target, Translation};
+// Translation targets are configured with naga's own option types
+pub use naga;
+// Derived code names these through here, users don't have to depend on the same versions
+pub use wgpu;
+pub use bytemuck;
+pub use sampler::{Sampler, SamplerMode};
+pub use texture::{Texture, TextureKind, TextureMode};
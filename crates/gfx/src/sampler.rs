@@ -55,3 +55,38 @@ impl Sampler {
         }.into();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Docs stay on the handle
+    #[arcanize]
+    #[derive(Default)]
+    struct Handle {
+        value: u32,
+        name: String,
+    }
+
+    #[test]
+    fn clones_share_the_object() {
+        let handle: Handle = HandleInner { value: 1, name: String::from("a") }.into();
+        let clone = handle.clone();
+        assert_eq!(handle, clone);
+        assert_eq!((clone.value, clone.name.as_str()), (1, "a"));
+        assert_eq!(format!("{:?}", clone), "Handle { value: 1, name: \"a\" }");
+
+        // Equality and hashing go by the object, not its fields
+        let other: Handle = HandleInner { value: 1, name: String::from("a") }.into();
+        assert_ne!(handle, other);
+        let set = [handle, clone, other].into_iter().collect::<HashSet<_>>();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn derives_go_to_the_fields() {
+        let handle: Handle = HandleInner::default().into();
+        assert_eq!((handle.value, handle.name.as_str()), (0, ""));
+    }
+}
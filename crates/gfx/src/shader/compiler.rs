@@ -0,0 +1,39 @@
+use std::pin::pin;
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
+use crate::{Device, WgpuExt};
+use super::{Diagnostic, ShaderError, SourceMap};
+
+/// Parse a SPIR-V binary, there is no source to point into so errors only carry a message
+pub fn parse_spirv(binary: &[u8]) -> Result<naga::Module, ShaderError> {
+    return naga::front::spv::parse_u8_slice(binary, &naga::front::spv::Options::default()).map_err(|error| {
+        ShaderError::Parse(Box::new(Diagnostic::from(error.to_string())))
+    });
+}
+
+/// Create the wgpu module, catching device errors instead of letting them go to the uncaptured handler
+pub fn create_module(device: &Device, label: &str, map: Option<&SourceMap>, source: wgpu::ShaderSource) -> Result<wgpu::ShaderModule, ShaderError> {
+    device.device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.wgpu_create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: source,
+    });
+
+    // Native backends resolve error scopes immediately, on the web this would need a real executor
+    let error = match pin!(device.device.pop_error_scope()).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(error) => error,
+        Poll::Pending => {
+            log::warn!("Error scope for {} did not resolve immediately, compile errors will not be mapped", label);
+            None
+        }
+    };
+
+    return match error {
+        Some(error) => {
+            let message = error.to_string();
+            let message = map.map_or(message.clone(), |map| map.rewrite(&message));
+            Err(ShaderError::Validation(Box::new(Diagnostic::from(message))))
+        }
+        None => Ok(module),
+    };
+}
@@ -0,0 +1,93 @@
+use std::hash::{Hash, Hasher};
+use std::fmt::{Display, Formatter};
+use std::collections::BTreeMap;
+use super::Reflection;
+
+/// Values for a shader's `override` constants, set when a pipeline is created
+///
+/// Constants are keyed by their name, or by their `@id` written as a number.
+/// Like defines they're kept sorted, so the same set always hashes the same and
+/// can be used to cache the pipelines built with it.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderConstants {
+    values: BTreeMap<String, f64>,
+}
+
+impl ShaderConstants {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Set a constant, booleans are `0.0` or `1.0`
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<f64>) -> Self {
+        self.insert(name, value);
+        return self;
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<f64>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<f64> {
+        return self.values.remove(name);
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        return self.values.get(name).copied();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.values.is_empty();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        return self.values.iter().map(|(name, value)| (name.as_str(), *value));
+    }
+
+    /// Only the constants `reflection` declares
+    ///
+    /// wgpu refuses to create a pipeline given a constant the shader doesn't have,
+    /// which would happen whenever a shader falls back to `error.wgsl`.
+    pub fn declared_by(&self, reflection: &Reflection) -> Self {
+        let mut constants = Self::new();
+        for (name, value) in self.iter() {
+            match reflection.override_constant(name) {
+                Some(_) => constants.insert(name, value),
+                None => log::warn!("Ignoring constant {} the shader doesn't declare", name),
+            }
+        }
+        return constants;
+    }
+}
+
+// f64 isn't Eq, comparing the bits is enough to tell permutations apart
+impl PartialEq for ShaderConstants {
+    fn eq(&self, other: &Self) -> bool {
+        return self.values.len() == other.values.len() && self.values.iter().zip(&other.values).all(|(a, b)| {
+            a.0 == b.0 && a.1.to_bits() == b.1.to_bits()
+        });
+    }
+}
+
+impl Eq for ShaderConstants {}
+
+impl Hash for ShaderConstants {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for (name, value) in &self.values {
+            name.hash(state);
+            value.to_bits().hash(state);
+        }
+    }
+}
+
+impl Display for ShaderConstants {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i != 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", name, value)?;
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,274 @@
+mod compiler;
+mod constants;
+mod reflection;
+mod watcher;
+mod translate;
+
+use std::sync::Arc;
+use std::borrow::Cow;
+use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::fmt::Formatter;
+use crate::{Device, WgpuExt};
+use std::collections::HashMap;
+
+pub use constants::ShaderConstants;
+pub use watcher::ShaderWatcher;
+pub use compiler::create_module;
+pub use translate::{translate, Target, Translation};
+pub use reflection::{EntryPoint, Override, Reflection, Resource, Varying};
+pub use gfx_preprocessor::{Diagnostic, ShaderError, SourceLocation, SourceMap};
+pub use gfx_preprocessor::{Preprocessed, Preprocessor, ShaderDefines, ShaderRegistry, ShaderStage};
+
+#[derive(Eq, PartialEq)]
+#[derive(Debug, Copy, Clone)]
+pub enum ShaderSource {
+    Wgsl, Glsl,
+    Slang, Spirv,
+}
+
+/// A compiled shader module together with everything known about it
+///
+/// Reloading needs `&mut self`, put the shader behind a lock to reload it while it's shared.
+/// Only the caches filled in through `&self` have their own locks.
+pub struct Shader<'a> {
+    pub name: String,
+    pub device: &'a Device,
+    pub path: Option<PathBuf>,
+    pub source: Option<String>,
+    pub source_type: ShaderSource,
+    pub module: wgpu::ShaderModule,
+    pub imports: Option<Vec<PathBuf>>,
+    /// Maps lines of `source` back to `imports`
+    pub map: Option<SourceMap>,
+    pub defines: ShaderDefines,
+    pub reflection: Reflection,
+    /// naga's view of the shader, kept around for translating it to other languages
+    pub ir: naga::Module,
+    pub info: naga::valid::ModuleInfo,
+    /// Bumped on every successful reload, anything built from the shader should be rebuilt when it changes
+    pub generation: u64,
+    // Permutations of this shader compiled so far, see `variant`
+    variants: RwLock<HashMap<ShaderDefines, Arc<Shader<'a>>>>,
+    // Made on the first call to `create_bind_group_layouts`
+    layouts: RwLock<Option<Vec<wgpu::BindGroupLayout>>>,
+}
+
+/// What every source format comes down to before a module is made from it
+struct Loaded {
+    /// Only set for formats that go through the preprocessor
+    preprocessed: Option<Preprocessed>,
+    source: wgpu::ShaderSource<'static>,
+    source_type: ShaderSource,
+    ir: naga::Module,
+    info: naga::valid::ModuleInfo,
+}
+
+impl<'a> Shader<'a> {
+    pub fn new(device: &'a Device, name: String, path: PathBuf) -> Result<Self, ShaderError> {
+        return Self::with_defines(device, name, path, ShaderDefines::new());
+    }
+
+    pub fn with_defines(device: &'a Device, name: String, path: PathBuf, defines: ShaderDefines) -> Result<Self, ShaderError> {
+        log::info!("Loading shader: {:?} [{}]", &path, defines);
+        let loaded = Self::load(&path, &defines)?;
+        let reflection = Reflection::new(&loaded.ir, &loaded.info);
+        let (source, imports, map) = match loaded.preprocessed {
+            Some(shader) => (Some(shader.source), Some(shader.imports), Some(shader.map)),
+            None => (None, None, None),
+        };
+        let module = compiler::create_module(device, &name, map.as_ref(), loaded.source)?;
+
+        return Ok(Self {
+            name: name,
+            module: module,
+            reflection: reflection,
+            device: &device,
+            path: Some(path),
+            defines: defines,
+            map: map,
+            generation: 0,
+            variants: RwLock::default(),
+            layouts: RwLock::default(),
+            source: source,
+            imports: imports,
+            source_type: loaded.source_type,
+            ir: loaded.ir,
+            info: loaded.info,
+        });
+    }
+
+    /// Preprocess, parse and validate a shader file the same way `with_defines` does, without a device
+    pub fn validate(path: &Path, defines: ShaderDefines) -> Result<(naga::Module, naga::valid::ModuleInfo), ShaderError> {
+        let loaded = Self::load(path, &defines)?;
+        return Ok((loaded.ir, loaded.info));
+    }
+
+    /// Recompile from `path`, on failure the shader is left as it was
+    pub fn reload(&mut self) -> Result<(), ShaderError> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+
+        let shader = Shader::with_defines(self.device, self.name.clone(), path, self.defines.clone())?;
+        self.map = shader.map;
+        self.module = shader.module;
+        self.source = shader.source;
+        self.imports = shader.imports;
+        self.reflection = shader.reflection;
+        self.ir = shader.ir;
+        self.info = shader.info;
+        self.generation += 1;
+        // Variants and layouts were made from the old source
+        self.variants.get_mut().unwrap().clear();
+        *self.layouts.get_mut().unwrap() = None;
+        return Ok(());
+    }
+
+    /// Get this shader compiled with extra defines, each permutation is only compiled once
+    pub fn variant(&self, defines: &ShaderDefines) -> Result<Arc<Shader<'a>>, ShaderError> {
+        let mut merged = self.defines.clone();
+        for (name, value) in defines.iter() {
+            merged.insert(name, value);
+        }
+
+        if let Some(shader) = self.variants.read().unwrap().get(&merged) {
+            return Ok(shader.clone());
+        }
+        let path = self.path.clone().unwrap();
+        let name = format!("{} [{}]", self.name, merged.key());
+        let shader = Arc::new(Shader::with_defines(self.device, name, path, merged.clone())?);
+        self.variants.write().unwrap().insert(merged, shader.clone());
+        return Ok(shader);
+    }
+
+    /// A layout for every bind group the shader uses, groups it skips get an empty layout
+    ///
+    /// The layouts are only made once, later calls return the same ones.
+    pub fn create_bind_group_layouts(&self) -> Vec<wgpu::BindGroupLayout> {
+        if let Some(layouts) = self.layouts.read().unwrap().as_ref() {
+            return layouts.clone();
+        }
+        let layouts = (0..self.reflection.group_count()).map(|group| {
+            let label = format!("{} Group {} Layout", self.name, group);
+            self.device.wgpu_create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(&label),
+                entries: &self.reflection.layout_entries(group),
+            })
+        }).collect::<Vec<_>>();
+        return self.layouts.write().unwrap().get_or_insert(layouts).clone();
+    }
+
+    pub fn create_pipeline_layout(&self, bind_group_layouts: &[&wgpu::BindGroupLayout]) -> wgpu::PipelineLayout {
+        return self.device.wgpu_create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            push_constant_ranges: &[],
+            bind_group_layouts: bind_group_layouts,
+            label: Some(&format!("{} Pipeline Layout", self.name)),
+        });
+    }
+
+    /// Write the shader out in another language, see `Target` for the options
+    pub fn translate(&self, target: &Target) -> Result<Translation, ShaderError> {
+        return translate::translate(&self.ir, &self.info, target);
+    }
+
+    pub fn get_entry(&self, stage: ShaderStage) -> Option<&str> {
+        return self.reflection.entry_point(stage).map(|entry| entry.name.as_str());
+    }
+
+    /// Read a shader in any supported format and check it with naga
+    fn load(path: &Path, defines: &ShaderDefines) -> Result<Loaded, ShaderError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("wgsl") => {
+                let shader = Self::parse_shader(path, defines.clone())?;
+                // Catch errors with naga first so they can be reported against the original files
+                let (naga, info) = shader.compile()?;
+                return Ok(Loaded {
+                    source: wgpu::ShaderSource::Wgsl(shader.source.clone().into()),
+                    source_type: ShaderSource::Wgsl,
+                    preprocessed: Some(shader),
+                    ir: naga,
+                    info: info,
+                });
+            }
+            Some(extension @ ("vert" | "frag" | "comp" | "glsl")) => {
+                let shader = Self::parse_shader(path, defines.clone())?;
+                let Some(stage) = shader.stage.or(ShaderStage::from_extension(extension)) else {
+                    return Err(ShaderError::UnknownStage { path: path.to_path_buf() });
+                };
+
+                let (naga, info) = shader.compile_glsl(stage)?;
+                return Ok(Loaded {
+                    // Hand wgpu the module we already have instead of making it parse the GLSL again
+                    source: wgpu::ShaderSource::Naga(Cow::Owned(naga.clone())),
+                    source_type: ShaderSource::Glsl,
+                    preprocessed: Some(shader),
+                    ir: naga,
+                    info: info,
+                });
+            }
+            Some("spv") => {
+                if !defines.is_empty() {
+                    return Err(ShaderError::UnsupportedDefines { path: path.to_path_buf() });
+                }
+                let binary = std::fs::read(path).map_err(|error| ShaderError::Io {
+                    error: error,
+                    path: path.to_path_buf(),
+                    imported_from: None,
+                })?;
+                // Binaries from other toolchains get the same checks as our own shaders
+                let naga = compiler::parse_spirv(&binary)?;
+                let info = gfx_preprocessor::validate(&naga, None)?;
+                return Ok(Loaded {
+                    source: wgpu::ShaderSource::SpirV(Cow::Owned(wgpu::util::make_spirv_raw(&binary).into_owned())),
+                    source_type: ShaderSource::Spirv,
+                    preprocessed: None,
+                    ir: naga,
+                    info: info,
+                });
+            }
+            _ => return Err(ShaderError::UnsupportedFormat { path: path.to_path_buf() }),
+        };
+    }
+
+    fn parse_shader(source: &Path, defines: ShaderDefines) -> Result<Preprocessed, ShaderError> {
+        return Preprocessor::with_defines(defines).process(source);
+    }
+}
+
+impl<'a> std::fmt::Debug for Shader<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shader")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("reflection", &self.reflection)
+            .field("imports", &self.imports)
+            .field("defines", &self.defines)
+            .field("generation", &self.generation)
+            .field("source type", &self.source_type)
+            .field("device", &(&self.device as *const _))
+            .field("module", &(&self.module as *const _))
+            .field("source", &(&self.source as *const _))
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spirv_rejects_defines() {
+        let dir = std::env::temp_dir().join(format!("gfx-ne-spirv-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("fill.wgsl"), "@fragment fn main() -> @location(0) vec4<f32> { return vec4(1.0); }").unwrap();
+        let (ir, info) = Shader::validate(&dir.join("fill.wgsl"), ShaderDefines::new()).unwrap();
+        let binary = translate(&ir, &info, &Target::Spirv(Default::default())).unwrap();
+        std::fs::write(dir.join("fill.spv"), binary.to_bytes()).unwrap();
+
+        assert!(Shader::validate(&dir.join("fill.spv"), ShaderDefines::new()).is_ok());
+        let defines = ShaderDefines::new().define("SHADOWS");
+        assert!(matches!(Shader::validate(&dir.join("fill.spv"), defines), Err(ShaderError::UnsupportedDefines { .. })));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
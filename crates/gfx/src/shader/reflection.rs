@@ -0,0 +1,542 @@
+use super::ShaderStage;
+use std::num::{NonZeroU32, NonZeroU64};
+use naga::valid::ModuleInfo;
+
+/// Everything the pipeline needs to know about a shader, read from the naga module
+#[derive(Debug, Clone, Default)]
+pub struct Reflection {
+    pub entry_points: Vec<EntryPoint>,
+    /// Every `@group/@binding` resource, sorted by group and binding
+    pub resources: Vec<Resource>,
+    /// Every `override` constant, in declaration order
+    pub overrides: Vec<Override>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryPoint {
+    pub name: String,
+    pub stage: ShaderStage,
+    /// Only set for compute shaders
+    pub workgroup_size: Option<[u32; 3]>,
+    /// `@location` inputs, for a vertex shader these are the vertex attributes
+    pub inputs: Vec<Varying>,
+    /// `@location` outputs, for a fragment shader these are the color targets
+    pub outputs: Vec<Varying>,
+}
+
+/// A `@location` value passed between stages, struct members are flattened
+#[derive(Debug, Clone)]
+#[derive(PartialEq, Eq)]
+pub struct Varying {
+    pub location: u32,
+    pub name: Option<String>,
+    /// `None` for types that can't be a vertex attribute
+    pub format: Option<wgpu::VertexFormat>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(PartialEq, Eq)]
+pub struct Resource {
+    pub group: u32,
+    pub binding: u32,
+    pub name: Option<String>,
+    pub ty: wgpu::BindingType,
+    /// Size of the bound type in bytes, for runtime sized arrays this includes one element
+    pub size: Option<u64>,
+    /// Set for binding arrays, `None` inside means the array is unsized
+    pub count: Option<Option<u32>>,
+    /// The stages whose entry points actually use the resource
+    pub visibility: wgpu::ShaderStages,
+}
+
+/// A pipeline overridable constant, `@id(n) override name: T = default;`
+#[derive(Debug, Clone)]
+#[derive(PartialEq)]
+pub struct Override {
+    pub id: Option<u16>,
+    pub name: Option<String>,
+    pub scalar: naga::Scalar,
+    /// Only set when the default is a plain literal, `None` also means it has to be given a value
+    pub default: Option<f64>,
+}
+
+impl Override {
+    /// The key the pipeline constants use for this override, its id when it has one
+    pub fn key(&self) -> Option<String> {
+        return self.id.map(|id| id.to_string()).or_else(|| self.name.clone());
+    }
+
+    /// Whether the override has no default and has to be set when creating a pipeline
+    pub fn is_required(&self) -> bool {
+        return self.default.is_none();
+    }
+}
+
+impl Reflection {
+    pub fn new(module: &naga::Module, info: &ModuleInfo) -> Self {
+        let entry_points = module.entry_points.iter().filter_map(|entry| {
+            let stage = match entry.stage {
+                naga::ShaderStage::Vertex => ShaderStage::Vertex,
+                naga::ShaderStage::Compute => ShaderStage::Compute,
+                naga::ShaderStage::Fragment => ShaderStage::Fragment,
+                stage => {
+                    log::warn!("Skipping entry point {} with unsupported stage {:?}", entry.name, stage);
+                    return None;
+                }
+            };
+
+            let mut inputs = Vec::new();
+            for argument in &entry.function.arguments {
+                Self::collect_varyings(module, argument.name.as_ref(), argument.ty, argument.binding.as_ref(), &mut inputs);
+            }
+            let mut outputs = Vec::new();
+            if let Some(result) = &entry.function.result {
+                Self::collect_varyings(module, None, result.ty, result.binding.as_ref(), &mut outputs);
+            }
+
+            return Some(EntryPoint {
+                stage: stage,
+                inputs: inputs,
+                outputs: outputs,
+                name: entry.name.clone(),
+                workgroup_size: (stage == ShaderStage::Compute).then_some(entry.workgroup_size),
+            });
+        }).collect::<Vec<_>>();
+
+        let mut resources = Vec::new();
+        for (handle, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+
+            let mut visibility = wgpu::ShaderStages::NONE;
+            for (i, entry) in module.entry_points.iter().enumerate() {
+                if !info.get_entry_point(i)[handle].is_empty() {
+                    visibility |= match entry.stage {
+                        naga::ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+                        naga::ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+                        naga::ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+                        naga::ShaderStage::Task => wgpu::ShaderStages::TASK,
+                        naga::ShaderStage::Mesh => wgpu::ShaderStages::MESH,
+                    };
+                }
+            }
+
+            // Binding arrays describe the element, the array only sets the count
+            let (ty, count) = match module.types[global.ty].inner {
+                naga::TypeInner::BindingArray { base, size } => match size {
+                    naga::ArraySize::Constant(size) => (base, Some(Some(size.get()))),
+                    _ => (base, Some(None)),
+                },
+                _ => (global.ty, None),
+            };
+            let size = match global.space {
+                naga::AddressSpace::Uniform | naga::AddressSpace::Storage { .. } => {
+                    Some(module.types[ty].inner.size(module.to_ctx()) as u64)
+                }
+                _ => None,
+            };
+            let Some(binding_type) = Self::binding_type(&module.types[ty].inner, global.space, size) else {
+                log::warn!("Skipping resource {:?} at @group({}) @binding({}) with unsupported type", global.name, binding.group, binding.binding);
+                continue;
+            };
+
+            resources.push(Resource {
+                size: size,
+                count: count,
+                ty: binding_type,
+                visibility: visibility,
+                group: binding.group,
+                binding: binding.binding,
+                name: global.name.clone(),
+            });
+        }
+        resources.sort_by_key(|resource| (resource.group, resource.binding));
+
+        let overrides = module.overrides.iter().map(|(_, constant)| {
+            let scalar = match module.types[constant.ty].inner {
+                naga::TypeInner::Scalar(scalar) => scalar,
+                _ => unreachable!("overrides are always scalars"),
+            };
+            let default = constant.init.and_then(|init| match module.global_expressions[init] {
+                naga::Expression::Literal(literal) => Self::literal_value(literal),
+                _ => None,
+            });
+
+            return Override {
+                scalar: scalar,
+                default: default,
+                id: constant.id,
+                name: constant.name.clone(),
+            };
+        }).collect::<Vec<_>>();
+
+        return Self {
+            overrides: overrides,
+            resources: resources,
+            entry_points: entry_points,
+        };
+    }
+
+    /// The first entry point for `stage`
+    pub fn entry_point(&self, stage: ShaderStage) -> Option<&EntryPoint> {
+        return self.entry_points.iter().find(|entry| entry.stage == stage);
+    }
+
+    pub fn resource(&self, group: u32, binding: u32) -> Option<&Resource> {
+        return self.resources.iter().find(|resource| resource.group == group && resource.binding == binding);
+    }
+
+    /// Find an override by its `@id` or name, the same keys pipeline constants use
+    pub fn override_constant(&self, key: &str) -> Option<&Override> {
+        return self.overrides.iter().find(|constant| {
+            constant.name.as_deref() == Some(key) || constant.id.is_some_and(|id| id.to_string() == key)
+        });
+    }
+
+    /// Every resource in `group`, sorted by binding
+    pub fn group(&self, group: u32) -> impl Iterator<Item = &Resource> {
+        return self.resources.iter().filter(move |resource| resource.group == group);
+    }
+
+    /// One more than the highest group used, ie the number of bind group layouts a pipeline needs
+    pub fn group_count(&self) -> u32 {
+        return self.resources.iter().map(|resource| resource.group + 1).max().unwrap_or(0);
+    }
+
+    /// Every stage the module has an entry point for
+    pub fn stages(&self) -> wgpu::ShaderStages {
+        return self.entry_points.iter().fold(wgpu::ShaderStages::NONE, |stages, entry| stages | stage_flags(entry.stage));
+    }
+
+    /// The layout entries for `group`, sorted by binding
+    ///
+    /// Entries are visible to every stage in the module rather than only the ones
+    /// using them, that way layouts for the same bindings match across shaders.
+    /// Vertex shaders can't write to storage, so writable storage skips that stage.
+    pub fn layout_entries(&self, group: u32) -> Vec<wgpu::BindGroupLayoutEntry> {
+        let stages = self.stages();
+        return self.group(group).map(|resource| {
+            if resource.count == Some(None) {
+                log::warn!("{:?} is an unsized binding array, its count has to be set by hand", resource.name);
+            }
+            let visibility = match is_writable(&resource.ty) {
+                true => stages - wgpu::ShaderStages::VERTEX,
+                false => stages,
+            };
+            wgpu::BindGroupLayoutEntry {
+                ty: resource.ty,
+                visibility: visibility,
+                binding: resource.binding,
+                count: resource.count.flatten().and_then(NonZeroU32::new),
+            }
+        }).collect();
+    }
+
+    /// Check a layout made outside the shader, e.g. from a `BindGroupState`, against `group`
+    ///
+    /// The layout can have bindings the shader doesn't use, but every binding it does use
+    /// has to be there with a compatible type and be visible to the stages using it.
+    pub fn check_layout(&self, group: u32, entries: &[wgpu::BindGroupLayoutEntry]) -> Result<(), String> {
+        for resource in self.group(group) {
+            let name = resource.name.as_deref().unwrap_or("<unnamed>");
+            let Some(entry) = entries.iter().find(|entry| entry.binding == resource.binding) else {
+                return Err(format!("`{}` at @group({}) @binding({}) is missing from the layout", name, group, resource.binding));
+            };
+            if !entry.visibility.contains(resource.visibility) {
+                return Err(format!("`{}` is used in {:?} but only visible to {:?}", name, resource.visibility, entry.visibility));
+            }
+            if !Self::compatible(&resource.ty, &entry.ty, resource.size) {
+                return Err(format!("`{}` is {:?} in the shader but {:?} in the layout", name, resource.ty, entry.ty));
+            }
+        }
+        return Ok(());
+    }
+
+    /// Whether a layout entry of type `layout` can be bound to a shader resource of type `shader`
+    fn compatible(shader: &wgpu::BindingType, layout: &wgpu::BindingType, size: Option<u64>) -> bool {
+        return match (shader, layout) {
+            (wgpu::BindingType::Buffer { ty, .. }, wgpu::BindingType::Buffer { ty: layout_ty, min_binding_size, .. }) => {
+                // Anything smaller than what the shader reads is rejected when binding
+                ty == layout_ty && min_binding_size.is_none_or(|min| size.is_none_or(|size| min.get() >= size))
+            }
+            // Shaders can't tell filtering and non-filtering samplers apart, only comparison ones
+            (wgpu::BindingType::Sampler(sampler), wgpu::BindingType::Sampler(layout_sampler)) => {
+                (*sampler == wgpu::SamplerBindingType::Comparison) == (*layout_sampler == wgpu::SamplerBindingType::Comparison)
+            }
+            (
+                wgpu::BindingType::Texture { sample_type, view_dimension, multisampled },
+                wgpu::BindingType::Texture { sample_type: layout_sample_type, view_dimension: layout_view_dimension, multisampled: layout_multisampled },
+            ) => {
+                // wgpu sorts sample types into the same classes as naga, where filterable
+                // or not is still a float and a depth texture never passes for one
+                let sample_type_matches = matches!(
+                    (sample_type, layout_sample_type),
+                    (wgpu::TextureSampleType::Float { .. }, wgpu::TextureSampleType::Float { .. })
+                        | (wgpu::TextureSampleType::Sint, wgpu::TextureSampleType::Sint)
+                        | (wgpu::TextureSampleType::Uint, wgpu::TextureSampleType::Uint)
+                        | (wgpu::TextureSampleType::Depth, wgpu::TextureSampleType::Depth)
+                );
+                sample_type_matches && view_dimension == layout_view_dimension && multisampled == layout_multisampled
+            }
+            _ => shader == layout,
+        };
+    }
+
+    fn collect_varyings(module: &naga::Module, name: Option<&String>, ty: naga::Handle<naga::Type>, binding: Option<&naga::Binding>, varyings: &mut Vec<Varying>) {
+        match binding {
+            Some(naga::Binding::Location { location, .. }) => varyings.push(Varying {
+                location: *location,
+                name: name.cloned(),
+                format: Self::vertex_format(&module.types[ty].inner),
+            }),
+            Some(naga::Binding::BuiltIn(_)) => {}
+            // Only structs can be passed without a binding, their members carry them instead
+            None => if let naga::TypeInner::Struct { members, .. } = &module.types[ty].inner {
+                for member in members {
+                    Self::collect_varyings(module, member.name.as_ref(), member.ty, member.binding.as_ref(), varyings);
+                }
+            },
+        }
+    }
+
+    fn literal_value(literal: naga::Literal) -> Option<f64> {
+        return match literal {
+            naga::Literal::F64(value) => Some(value),
+            naga::Literal::F32(value) => Some(value as f64),
+            naga::Literal::F16(value) => Some(f64::from(value)),
+            naga::Literal::U32(value) => Some(value as f64),
+            naga::Literal::I32(value) => Some(value as f64),
+            naga::Literal::U64(value) => Some(value as f64),
+            naga::Literal::I64(value) => Some(value as f64),
+            naga::Literal::Bool(value) => Some(value as u8 as f64),
+            _ => None,
+        };
+    }
+
+    fn vertex_format(inner: &naga::TypeInner) -> Option<wgpu::VertexFormat> {
+        use wgpu::VertexFormat as F;
+        let (size, scalar) = match *inner {
+            naga::TypeInner::Scalar(scalar) => (1, scalar),
+            naga::TypeInner::Vector { size, scalar } => (size as u8, scalar),
+            _ => return None,
+        };
+        return match (scalar.kind, scalar.width, size) {
+            (naga::ScalarKind::Float, 4, 1) => Some(F::Float32),
+            (naga::ScalarKind::Float, 4, 2) => Some(F::Float32x2),
+            (naga::ScalarKind::Float, 4, 3) => Some(F::Float32x3),
+            (naga::ScalarKind::Float, 4, 4) => Some(F::Float32x4),
+            (naga::ScalarKind::Float, 2, 1) => Some(F::Float16),
+            (naga::ScalarKind::Float, 2, 2) => Some(F::Float16x2),
+            (naga::ScalarKind::Float, 2, 4) => Some(F::Float16x4),
+            (naga::ScalarKind::Float, 8, 1) => Some(F::Float64),
+            (naga::ScalarKind::Float, 8, 2) => Some(F::Float64x2),
+            (naga::ScalarKind::Float, 8, 3) => Some(F::Float64x3),
+            (naga::ScalarKind::Float, 8, 4) => Some(F::Float64x4),
+            (naga::ScalarKind::Uint, 4, 1) => Some(F::Uint32),
+            (naga::ScalarKind::Uint, 4, 2) => Some(F::Uint32x2),
+            (naga::ScalarKind::Uint, 4, 3) => Some(F::Uint32x3),
+            (naga::ScalarKind::Uint, 4, 4) => Some(F::Uint32x4),
+            (naga::ScalarKind::Sint, 4, 1) => Some(F::Sint32),
+            (naga::ScalarKind::Sint, 4, 2) => Some(F::Sint32x2),
+            (naga::ScalarKind::Sint, 4, 3) => Some(F::Sint32x3),
+            (naga::ScalarKind::Sint, 4, 4) => Some(F::Sint32x4),
+            _ => None,
+        };
+    }
+
+    fn binding_type(inner: &naga::TypeInner, space: naga::AddressSpace, size: Option<u64>) -> Option<wgpu::BindingType> {
+        let buffer = |ty: wgpu::BufferBindingType| wgpu::BindingType::Buffer {
+            ty: ty,
+            has_dynamic_offset: false,
+            min_binding_size: size.and_then(NonZeroU64::new),
+        };
+        match space {
+            naga::AddressSpace::Uniform => return Some(buffer(wgpu::BufferBindingType::Uniform)),
+            naga::AddressSpace::Storage { access } => {
+                let read_only = !access.contains(naga::StorageAccess::STORE);
+                return Some(buffer(wgpu::BufferBindingType::Storage { read_only: read_only }));
+            }
+            naga::AddressSpace::Handle => {}
+            _ => return None,
+        }
+
+        return match *inner {
+            naga::TypeInner::Sampler { comparison: true } => Some(wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison)),
+            naga::TypeInner::Sampler { comparison: false } => Some(wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)),
+            naga::TypeInner::AccelerationStructure { vertex_return } => Some(wgpu::BindingType::AccelerationStructure { vertex_return: vertex_return }),
+            naga::TypeInner::Image { dim, arrayed, class } => {
+                let view_dimension = match (dim, arrayed) {
+                    (naga::ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+                    (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+                    (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+                    (naga::ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+                    (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+                    (naga::ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+                };
+                match class {
+                    naga::ImageClass::External => Some(wgpu::BindingType::ExternalTexture),
+                    naga::ImageClass::Depth { multi } => Some(wgpu::BindingType::Texture {
+                        multisampled: multi,
+                        view_dimension: view_dimension,
+                        sample_type: wgpu::TextureSampleType::Depth,
+                    }),
+                    naga::ImageClass::Sampled { kind, multi } => Some(wgpu::BindingType::Texture {
+                        multisampled: multi,
+                        view_dimension: view_dimension,
+                        sample_type: match kind {
+                            naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                            naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                            // Whether the texture can be filtered depends on the format, not the shader
+                            _ => wgpu::TextureSampleType::Float { filterable: !multi },
+                        },
+                    }),
+                    naga::ImageClass::Storage { format, access } => Some(wgpu::BindingType::StorageTexture {
+                        view_dimension: view_dimension,
+                        format: Self::storage_format(format),
+                        access: match (access.contains(naga::StorageAccess::LOAD), access.contains(naga::StorageAccess::STORE)) {
+                            _ if access.contains(naga::StorageAccess::ATOMIC) => wgpu::StorageTextureAccess::Atomic,
+                            (true, true) => wgpu::StorageTextureAccess::ReadWrite,
+                            (true, false) => wgpu::StorageTextureAccess::ReadOnly,
+                            (false, _) => wgpu::StorageTextureAccess::WriteOnly,
+                        },
+                    }),
+                }
+            }
+            _ => None,
+        };
+    }
+
+    fn storage_format(format: naga::StorageFormat) -> wgpu::TextureFormat {
+        use naga::StorageFormat as S;
+        use wgpu::TextureFormat as T;
+        return match format {
+            S::R8Unorm => T::R8Unorm,
+            S::R8Snorm => T::R8Snorm,
+            S::R8Uint => T::R8Uint,
+            S::R8Sint => T::R8Sint,
+            S::R16Uint => T::R16Uint,
+            S::R16Sint => T::R16Sint,
+            S::R16Float => T::R16Float,
+            S::Rg8Unorm => T::Rg8Unorm,
+            S::Rg8Snorm => T::Rg8Snorm,
+            S::Rg8Uint => T::Rg8Uint,
+            S::Rg8Sint => T::Rg8Sint,
+            S::R32Uint => T::R32Uint,
+            S::R32Sint => T::R32Sint,
+            S::R32Float => T::R32Float,
+            S::Rg16Uint => T::Rg16Uint,
+            S::Rg16Sint => T::Rg16Sint,
+            S::Rg16Float => T::Rg16Float,
+            S::Rgba8Unorm => T::Rgba8Unorm,
+            S::Rgba8Snorm => T::Rgba8Snorm,
+            S::Rgba8Uint => T::Rgba8Uint,
+            S::Rgba8Sint => T::Rgba8Sint,
+            S::Bgra8Unorm => T::Bgra8Unorm,
+            S::Rgb10a2Uint => T::Rgb10a2Uint,
+            S::Rgb10a2Unorm => T::Rgb10a2Unorm,
+            S::Rg11b10Ufloat => T::Rg11b10Ufloat,
+            S::R64Uint => T::R64Uint,
+            S::Rg32Uint => T::Rg32Uint,
+            S::Rg32Sint => T::Rg32Sint,
+            S::Rg32Float => T::Rg32Float,
+            S::Rgba16Uint => T::Rgba16Uint,
+            S::Rgba16Sint => T::Rgba16Sint,
+            S::Rgba16Float => T::Rgba16Float,
+            S::Rgba32Uint => T::Rgba32Uint,
+            S::Rgba32Sint => T::Rgba32Sint,
+            S::Rgba32Float => T::Rgba32Float,
+            S::R16Unorm => T::R16Unorm,
+            S::R16Snorm => T::R16Snorm,
+            S::Rg16Unorm => T::Rg16Unorm,
+            S::Rg16Snorm => T::Rg16Snorm,
+            S::Rgba16Unorm => T::Rgba16Unorm,
+            S::Rgba16Snorm => T::Rgba16Snorm,
+        };
+    }
+}
+
+/// Whether a binding can be written to, which vertex shaders aren't allowed to do
+fn is_writable(ty: &wgpu::BindingType) -> bool {
+    return match ty {
+        wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Storage { read_only }, .. } => !read_only,
+        wgpu::BindingType::StorageTexture { access, .. } => *access != wgpu::StorageTextureAccess::ReadOnly,
+        _ => false,
+    };
+}
+
+/// `ShaderStage` lives in the preprocessor, which doesn't know about wgpu
+fn stage_flags(stage: ShaderStage) -> wgpu::ShaderStages {
+    return match stage {
+        ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+        ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+        ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reflect(wgsl: &str) -> Reflection {
+        let module = naga::front::wgsl::parse_str(wgsl).unwrap_or_else(|error| panic!("{}\n{}", error, wgsl));
+        let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+        .unwrap();
+        return Reflection::new(&module, &info);
+    }
+
+    fn texture(sample_type: wgpu::TextureSampleType) -> wgpu::BindingType {
+        return wgpu::BindingType::Texture {
+            sample_type: sample_type,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        };
+    }
+
+    #[test]
+    fn writable_storage_is_not_visible_to_vertex() {
+        let reflection = reflect("
+            @group(0) @binding(0) var<storage, read_write> counts: array<u32>;
+            @group(0) @binding(1) var<storage, read> positions: array<vec4<f32>>;
+            @group(0) @binding(2) var output: texture_storage_2d<rgba8unorm, write>;
+
+            @vertex fn vs(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+                return positions[i];
+            }
+            @fragment fn fs() -> @location(0) vec4<f32> {
+                return vec4<f32>(f32(counts[0]));
+            }
+            @compute @workgroup_size(1) fn cs() {
+                counts[0] = 1u;
+                textureStore(output, vec2<i32>(0), vec4<f32>(1.0));
+            }
+        ");
+        let entries = reflection.layout_entries(0);
+        assert_eq!(entries[0].visibility, wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE);
+        assert_eq!(entries[1].visibility, wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE);
+        assert_eq!(entries[2].visibility, wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE);
+        assert_eq!(reflection.check_layout(0, &entries), Ok(()));
+    }
+
+    #[test]
+    fn texture_sample_types() {
+        use wgpu::TextureSampleType as T;
+        let float = texture(T::Float { filterable: true });
+        assert!(Reflection::compatible(&float, &texture(T::Float { filterable: false }), None));
+        assert!(Reflection::compatible(&texture(T::Sint), &texture(T::Sint), None));
+        assert!(Reflection::compatible(&texture(T::Depth), &texture(T::Depth), None));
+        assert!(!Reflection::compatible(&float, &texture(T::Uint), None));
+        assert!(!Reflection::compatible(&texture(T::Sint), &texture(T::Uint), None));
+        // wgpu rejects a depth texture in the shader with a float layout and the other way around
+        assert!(!Reflection::compatible(&float, &texture(T::Depth), None));
+        assert!(!Reflection::compatible(&texture(T::Depth), &float, None));
+
+        let array = wgpu::BindingType::Texture {
+            sample_type: T::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2Array,
+            multisampled: false,
+        };
+        assert!(!Reflection::compatible(&float, &array, None));
+    }
+}
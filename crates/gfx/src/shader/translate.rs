@@ -0,0 +1,100 @@
+use naga::back::{glsl, hlsl, msl, spv};
+use super::{ShaderError, ShaderStage};
+
+/// A naga backend a shader can be translated to, along with its options
+#[derive(Debug, Clone)]
+pub enum Target {
+    Spirv(spv::Options<'static>),
+    Hlsl(hlsl::Options),
+    Msl(msl::Options),
+    /// GLSL only has a single `main`, so one stage is translated at a time
+    Glsl(glsl::Options, ShaderStage),
+}
+
+impl Target {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Target::Spirv(_) => "SPIR-V",
+            Target::Hlsl(_) => "HLSL",
+            Target::Msl(_) => "MSL",
+            Target::Glsl(..) => "GLSL",
+        };
+    }
+
+    /// The extension translated files usually have
+    pub fn extension(&self) -> &'static str {
+        return match self {
+            Target::Spirv(_) => "spv",
+            Target::Hlsl(_) => "hlsl",
+            Target::Msl(_) => "metal",
+            Target::Glsl(_, ShaderStage::Vertex) => "vert",
+            Target::Glsl(_, ShaderStage::Compute) => "comp",
+            Target::Glsl(_, ShaderStage::Fragment) => "frag",
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Translation {
+    Text(String),
+    Binary(Vec<u32>),
+}
+
+impl Translation {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return match self {
+            Translation::Text(text) => text.clone().into_bytes(),
+            Translation::Binary(words) => bytemuck::cast_slice(words).to_vec(),
+        };
+    }
+}
+
+/// Translate a validated module with one of naga's backends
+pub fn translate(module: &naga::Module, info: &naga::valid::ModuleInfo, target: &Target) -> Result<Translation, ShaderError> {
+    let error = |message: String| ShaderError::Translation {
+        message: message,
+        target: target.name(),
+    };
+
+    // Overrides have to be resolved first, this uses their defaults
+    let constants = naga::back::PipelineConstants::default();
+    let (module, info) = naga::back::pipeline_constants::process_overrides(module, info, None, &constants)
+        .map_err(|e| error(e.to_string()))?;
+
+    return match target {
+        Target::Spirv(options) => {
+            let words = spv::write_vec(&module, &info, options, None).map_err(|e| error(e.to_string()))?;
+            Ok(Translation::Binary(words))
+        }
+        Target::Hlsl(options) => {
+            let mut source = String::new();
+            let pipeline = hlsl::PipelineOptions::default();
+            hlsl::Writer::new(&mut source, options, &pipeline).write(&module, &info, None)
+                .map_err(|e| error(e.to_string()))?;
+            Ok(Translation::Text(source))
+        }
+        Target::Msl(options) => {
+            let (source, _) = msl::write_string(&module, &info, options, &msl::PipelineOptions::default())
+                .map_err(|e| error(e.to_string()))?;
+            Ok(Translation::Text(source))
+        }
+        Target::Glsl(options, stage) => {
+            let stage = naga::ShaderStage::from(*stage);
+            let Some(entry) = module.entry_points.iter().find(|entry| entry.stage == stage) else {
+                return Err(error(format!("no {:?} entry point", stage)));
+            };
+            let pipeline = glsl::PipelineOptions {
+                multiview: None,
+                shader_stage: stage,
+                entry_point: entry.name.clone(),
+            };
+
+            let mut source = String::new();
+            let policies = naga::proc::BoundsCheckPolicies::default();
+            glsl::Writer::new(&mut source, &module, &info, options, &pipeline, policies)
+                .and_then(|mut writer| writer.write())
+                .map_err(|e| error(e.to_string()))?;
+            Ok(Translation::Text(source))
+        }
+    };
+}
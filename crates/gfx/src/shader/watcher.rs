@@ -0,0 +1,126 @@
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::collections::{HashMap, HashSet};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Watches the files shaders were built from and reports which shaders need recompiling
+///
+/// Shaders are identified by whatever key the owner uses to look them up, and
+/// depend on every file in their `imports`. Directories are watched rather than
+/// the files themselves since most editors save by replacing the file.
+pub struct ShaderWatcher<K> {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    // Which shaders depend on each file
+    dependents: HashMap<PathBuf, HashSet<K>>,
+    // The files each shader depends on, so they can be replaced on recompile
+    dependencies: HashMap<K, Vec<PathBuf>>,
+    // Watched directories and how many files depend on them
+    directories: HashMap<PathBuf, usize>,
+}
+
+impl<K: Hash + Eq + Clone> ShaderWatcher<K> {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            // The receiver only goes away with the watcher
+            let _ = sender.send(event);
+        })?;
+
+        return Ok(Self {
+            watcher: watcher,
+            events: receiver,
+            dependents: HashMap::new(),
+            directories: HashMap::new(),
+            dependencies: HashMap::new(),
+        });
+    }
+
+    /// Start watching `files` for `key`, replacing whatever it depended on before
+    pub fn watch(&mut self, key: K, files: &[PathBuf]) {
+        self.unwatch(&key);
+
+        let files = files.iter().map(|file| Self::normalize(file)).collect::<Vec<_>>();
+        for file in &files {
+            let Some(directory) = file.parent() else {
+                continue;
+            };
+            let count = self.directories.entry(directory.to_path_buf()).or_insert(0);
+            if *count == 0 {
+                log::debug!("Watching {:?} for shader changes", directory);
+                if let Err(error) = self.watcher.watch(directory, RecursiveMode::NonRecursive) {
+                    log::warn!("Failed to watch {:?}: {}", directory, error);
+                }
+            }
+            *count += 1;
+            self.dependents.entry(file.clone()).or_default().insert(key.clone());
+        }
+        self.dependencies.insert(key, files);
+    }
+
+    pub fn unwatch(&mut self, key: &K) {
+        let Some(files) = self.dependencies.remove(key) else {
+            return;
+        };
+
+        for file in files {
+            if let Some(dependents) = self.dependents.get_mut(&file) {
+                dependents.remove(key);
+                if dependents.is_empty() {
+                    self.dependents.remove(&file);
+                }
+            }
+
+            let Some(directory) = file.parent() else {
+                continue;
+            };
+            if let Some(count) = self.directories.get_mut(directory) {
+                *count -= 1;
+                if *count == 0 {
+                    self.directories.remove(directory);
+                    let _ = self.watcher.unwatch(directory);
+                }
+            }
+        }
+    }
+
+    /// Every shader with a dependency that changed since the last call, never blocks
+    pub fn changed(&mut self) -> HashSet<K> {
+        let mut changed = HashSet::new();
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(error) => {
+                    log::warn!("Shader watcher error: {}", error);
+                    continue;
+                }
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+
+            for path in &event.paths {
+                if let Some(dependents) = self.dependents.get(&Self::normalize(path)) {
+                    log::info!("{:?} changed, {} shader(s) need recompiling", path, dependents.len());
+                    changed.extend(dependents.iter().cloned());
+                }
+            }
+        }
+        return changed;
+    }
+
+    fn normalize(path: &Path) -> PathBuf {
+        // Events come with absolute paths, imports may well be relative
+        return std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    }
+}
+
+impl<K> std::fmt::Debug for ShaderWatcher<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShaderWatcher")
+            .field("files", &self.dependents.keys())
+            .field("directories", &self.directories.keys())
+        .finish()
+    }
+}
@@ -1,51 +1,306 @@
+use gfx_macros::arcanize;
+use crate::{Device, WgpuExt};
+
+/// The shape of a texture, along with its layer or depth count
+#[derive(Debug, Clone, Copy)]
+#[derive(Hash, Eq, PartialEq)]
+pub enum TextureKind {
+    D2,
+    /// A number of 2D layers
+    D2Array(u32),
+    /// 6 square faces in the order +X, -X, +Y, -Y, +Z, -Z
+    Cube,
+    /// A number of cubes, each taking 6 layers
+    CubeArray(u32),
+    /// A depth
+    D3(u32),
+}
+
+impl TextureKind {
+    pub fn dimension(&self) -> wgpu::TextureDimension {
+        return match self {
+            TextureKind::D3(_) => wgpu::TextureDimension::D3,
+            _ => wgpu::TextureDimension::D2,
+        };
+    }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        return match self {
+            TextureKind::D2 => wgpu::TextureViewDimension::D2,
+            TextureKind::D2Array(_) => wgpu::TextureViewDimension::D2Array,
+            TextureKind::Cube => wgpu::TextureViewDimension::Cube,
+            TextureKind::CubeArray(_) => wgpu::TextureViewDimension::CubeArray,
+            TextureKind::D3(_) => wgpu::TextureViewDimension::D3,
+        };
+    }
+
+    /// Layers, or the depth of a 3D texture
+    pub fn depth_or_array_layers(&self) -> u32 {
+        return match self {
+            TextureKind::D2 => 1,
+            TextureKind::D2Array(layers) => *layers,
+            TextureKind::Cube => 6,
+            TextureKind::CubeArray(cubes) => cubes * 6,
+            TextureKind::D3(depth) => *depth,
+        };
+    }
+
+    /// How many layers there are to write or view, 3D textures only have one
+    pub fn layers(&self) -> u32 {
+        return match self {
+            TextureKind::D3(_) => 1,
+            _ => self.depth_or_array_layers(),
+        };
+    }
+}
+
+/// Everything about a texture other than its size
+#[derive(Debug, Clone, Copy)]
+#[derive(Hash, Eq, PartialEq)]
+pub struct TextureMode {
+    pub kind: TextureKind,
+    pub format: wgpu::TextureFormat,
+    pub usage: wgpu::TextureUsages,
+    /// Clamped to however many mips the size allows
+    pub mip_levels: u32,
+    pub sample_count: u32,
+}
+
+impl TextureMode {
+    /// Sampled in shaders and written from the CPU, without mips or multisampling
+    pub const fn new(kind: TextureKind, format: wgpu::TextureFormat) -> Self {
+        return Self {
+            kind: kind,
+            format: format,
+            mip_levels: 1,
+            sample_count: 1,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING.union(wgpu::TextureUsages::COPY_DST),
+        };
+    }
+}
+
+#[arcanize]
 pub struct Texture {
-    pub name: Option<String>,
+    pub device: Device,
+    pub mode: TextureMode,
+    pub label: Option<String>,
+    pub size: wgpu::Extent3d,
     pub texture: wgpu::Texture,
-    pub group: Option<wgpu::BindGroup>,
-    pub view: Option<wgpu::TextureView>,
+    /// Every mip and layer, viewed as the texture's kind
+    pub view: wgpu::TextureView,
 }
 
 impl Texture {
-    pub fn new(name: Option<String>) -> Self {
-        let name = name.unwrap_or("Unnamed Texture".into());
-        todo!()
-    }
-}
+    pub fn new(device: &Device, label: Option<String>, mode: TextureMode, width: u32, height: u32) -> Self {
+        if let TextureKind::Cube | TextureKind::CubeArray(_) = mode.kind {
+            assert_eq!(width, height, "Cube faces of {:?} have to be square", label);
+        }
 
-macro_rules! GenericEnum {
-    ($trait:ident $enum:ident $name:ident { $($variant:ident),*}) => {
-        pub enum $enum {
-            $($variant),*
+        let size = wgpu::Extent3d {
+            width: width,
+            height: height,
+            depth_or_array_layers: mode.kind.depth_or_array_layers(),
+        };
+        let mode = TextureMode {
+            mip_levels: mode.mip_levels.clamp(1, size.max_mips(mode.kind.dimension())),
+            ..mode
+        };
+        if mode.sample_count > 1 {
+            let unsupported = wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING;
+            assert!(mode.kind == TextureKind::D2 && mode.mip_levels == 1, "Multisampled {:?} has to be 2D without mips", label);
+            assert!(!mode.usage.intersects(unsupported), "Multisampled {:?} can only be rendered to and resolved", label);
         }
-        pub trait $trait {}
-        mod $name {
-            use super::{$trait, $enum};
-            $(pub struct $variant;)*
-            $(impl $trait for $variant{})*
-            $(impl From<$variant> for $enum {
-                fn from(_val: $variant) -> Self {
-                    return $enum::$variant;
-                }
-            })*
+        // Depth stencil formats can only be copied one aspect at a time, which `write` doesn't do
+        if mode.usage.contains(wgpu::TextureUsages::COPY_DST) {
+            assert!(mode.format.block_copy_size(None).is_some(), "{:?} can't be written as {:?}, leave out COPY_DST", label, mode.format);
         }
+
+        let texture = device.wgpu_create_texture(&wgpu::TextureDescriptor {
+            size: size,
+            usage: mode.usage,
+            format: mode.format,
+            view_formats: &[],
+            label: label.as_deref(),
+            sample_count: mode.sample_count,
+            mip_level_count: mode.mip_levels,
+            dimension: mode.kind.dimension(),
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: label.as_deref(),
+            dimension: Some(mode.kind.view_dimension()),
+            ..Default::default()
+        });
+
+        return TextureInner {
+            mode: mode,
+            size: size,
+            view: view,
+            label: label,
+            texture: texture,
+            device: device.clone(),
+        }.into();
     }
-}
 
-GenericEnum!(
-    BufferAccessType
-    BufferAccessTypes
-    BufferAccess {
-        Read,
-        Write,
-        RedWrite
+    /// A new, empty texture with the same mode, clones of the old one keep using the old one
+    ///
+    /// Bind groups holding the old view have to be recreated. The mip count is kept,
+    /// short of the new size not having room for as many.
+    pub fn resize(&self, width: u32, height: u32) -> Self {
+        return Self::new(&self.device, self.label.clone(), self.mode, width, height);
     }
-);
 
-pub struct Buffer<A = BufferAccess::Read> {
-    access: A,
+    /// Size of mip `level`, 3D textures shrink in depth too
+    pub fn mip_size(&self, level: u32) -> wgpu::Extent3d {
+        return self.size.mip_level_size(level, self.mode.kind.dimension());
+    }
+
+    /// Replace mip `level` of `layer` with tightly packed `data`, the whole volume for 3D textures
+    pub fn write(&self, level: u32, layer: u32, data: &[u8]) {
+        assert!(level < self.mode.mip_levels, "{:?} has no mip {}", self.label, level);
+        assert!(layer < self.mode.kind.layers(), "{:?} has no layer {}", self.label, layer);
+        assert!(self.mode.usage.contains(wgpu::TextureUsages::COPY_DST), "{:?} wasn't made with COPY_DST", self.label);
+
+        let size = self.mip_size(level).physical_size(self.mode.format);
+        let (block_width, block_height) = self.mode.format.block_dimensions();
+        // `new` made sure a COPY_DST texture has one
+        let block_size = self.mode.format.block_copy_size(None).unwrap();
+        let rows = size.height / block_height;
+        let bytes_per_row = size.width / block_width * block_size;
+        let depth = match self.mode.kind {
+            TextureKind::D3(_) => size.depth_or_array_layers,
+            _ => 1,
+        };
+        assert_eq!(data.len() as u32, bytes_per_row * rows * depth, "Mip {} of {:?} is the wrong size", level, self.label);
+
+        self.device.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                mip_level: level,
+                texture: &self.texture,
+                aspect: wgpu::TextureAspect::All,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: layer },
+            },
+            data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                rows_per_image: Some(rows),
+                bytes_per_row: Some(bytes_per_row),
+            },
+            wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: depth,
+            },
+        );
+    }
+
+    /// Write each of `mips` to `layer` starting from mip 0, like an `Image`'s levels
+    pub fn write_mips(&self, layer: u32, mips: &[impl AsRef<[u8]>]) {
+        for (level, data) in mips.iter().enumerate() {
+            self.write(level as u32, layer, data.as_ref());
+        }
+    }
+
+    /// Only mip `level`, with every layer, e.g. to render into while generating mips
+    pub fn mip_view(&self, level: u32) -> wgpu::TextureView {
+        return self.create_view(level..level + 1, 0..self.mode.kind.layers(), self.mode.kind.view_dimension());
+    }
+
+    /// A single layer as a 2D texture with all of its mips, like one face of a cube
+    pub fn layer_view(&self, layer: u32) -> wgpu::TextureView {
+        assert!(!matches!(self.mode.kind, TextureKind::D3(_)), "3D texture {:?} has no layers to view", self.label);
+        return self.create_view(0..self.mode.mip_levels, layer..layer + 1, wgpu::TextureViewDimension::D2);
+    }
+
+    pub fn create_view(&self, mips: std::ops::Range<u32>, layers: std::ops::Range<u32>, dimension: wgpu::TextureViewDimension) -> wgpu::TextureView {
+        return self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: self.label.as_deref(),
+            dimension: Some(dimension),
+            base_mip_level: mips.start,
+            base_array_layer: layers.start,
+            mip_level_count: Some(mips.len() as u32),
+            array_layer_count: Some(layers.len() as u32),
+            ..Default::default()
+        });
+    }
 }
 
-pub fn test() {
-    use BufferAccess::*;
-    let test: Buffer<Write>;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffers::test_device as device;
+
+    #[test]
+    fn layers() {
+        let kinds = [TextureKind::D2, TextureKind::D2Array(3), TextureKind::Cube, TextureKind::CubeArray(2), TextureKind::D3(8)];
+        let layers = kinds.map(|kind| (kind.depth_or_array_layers(), kind.layers()));
+        assert_eq!(layers, [(1, 1), (3, 3), (6, 6), (12, 12), (8, 1)]);
+    }
+
+    #[test]
+    fn mip_size() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+
+        let kinds = [TextureKind::D2, TextureKind::D2Array(3), TextureKind::Cube, TextureKind::CubeArray(2), TextureKind::D3(8)];
+        let sizes = kinds.map(|kind| {
+            let mode = TextureMode {
+                mip_levels: 32,
+                ..TextureMode::new(kind, wgpu::TextureFormat::Rgba8Unorm)
+            };
+            let texture = Texture::new(&device, None, mode, 64, 64);
+            let size = texture.mip_size(2);
+            return (texture.mode.mip_levels, size.width, size.height, size.depth_or_array_layers);
+        });
+        // Only 3D textures lose depth along with width and height
+        assert_eq!(sizes, [(7, 16, 16, 1), (7, 16, 16, 3), (7, 16, 16, 6), (7, 16, 16, 12), (7, 16, 16, 2)]);
+    }
+
+    #[test]
+    fn write_compressed() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+        if !device.device.features().contains(wgpu::Features::TEXTURE_COMPRESSION_BC) {
+            eprintln!("No BC compression, skipping");
+            return;
+        }
+
+        let mode = TextureMode {
+            mip_levels: 32,
+            ..TextureMode::new(TextureKind::D2, wgpu::TextureFormat::Bc1RgbaUnorm)
+        };
+        let texture = Texture::new(&device, None, mode, 64, 64);
+        // 8 bytes per 4x4 block, mips smaller than a block still take a whole one
+        texture.write(0, 0, &[0; 16 * 16 * 8]);
+        texture.write(5, 0, &[0; 8]);
+        texture.write(6, 0, &[0; 8]);
+
+        let uncompressed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| texture.write(0, 0, &[0; 64 * 64 * 4])));
+        assert!(uncompressed.is_err());
+    }
+
+    #[test]
+    fn multisampled_usage() {
+        let Some(device) = device() else {
+            eprintln!("No adapter, skipping");
+            return;
+        };
+
+        let target = TextureMode {
+            sample_count: 4,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            ..TextureMode::new(TextureKind::D2, wgpu::TextureFormat::Rgba8Unorm)
+        };
+        Texture::new(&device, None, target, 64, 64);
+
+        let sampled = TextureMode {
+            sample_count: 4,
+            ..TextureMode::new(TextureKind::D2, wgpu::TextureFormat::Rgba8Unorm)
+        };
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| Texture::new(&device, None, sampled, 64, 64)));
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,108 @@
+use bytemuck::{Pod, Zeroable};
+
+/// A vertex type and how it's laid out in a vertex buffer, usually `#[derive(VertexLayout)]`d
+pub trait VertexArrayObject: where Self: Pod + Zeroable {
+    const SIZE: usize = size_of::<Self>();
+    const VERTEX_BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static>;
+}
+
+/// A type that can be a vertex attribute, and the format the shader reads it as
+pub trait VertexAttribute {
+    const FORMAT: wgpu::VertexFormat;
+}
+
+/// Integers read as floats in `0.0..=1.0`, like `Unorm<[u8; 4]>` for colors
+#[repr(transparent)]
+#[derive(Debug, Default)]
+#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+pub struct Unorm<T>(pub T);
+
+/// Integers read as floats in `-1.0..=1.0`, like `Snorm<[i16; 4]>` for packed normals
+#[repr(transparent)]
+#[derive(Debug, Default)]
+#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+pub struct Snorm<T>(pub T);
+
+// SAFETY: both are `repr(transparent)` over a `T` that is already `Pod`
+unsafe impl<T: Zeroable> Zeroable for Unorm<T> {}
+unsafe impl<T: Pod> Pod for Unorm<T> {}
+unsafe impl<T: Zeroable> Zeroable for Snorm<T> {}
+unsafe impl<T: Pod> Pod for Snorm<T> {}
+
+macro_rules! vertex_attributes {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(impl VertexAttribute for $ty {
+            const FORMAT: wgpu::VertexFormat = wgpu::VertexFormat::$format;
+        })*
+    };
+}
+
+vertex_attributes!(
+    u8 => Uint8, [u8; 2] => Uint8x2, [u8; 4] => Uint8x4,
+    i8 => Sint8, [i8; 2] => Sint8x2, [i8; 4] => Sint8x4,
+    u16 => Uint16, [u16; 2] => Uint16x2, [u16; 4] => Uint16x4,
+    i16 => Sint16, [i16; 2] => Sint16x2, [i16; 4] => Sint16x4,
+    u32 => Uint32, [u32; 2] => Uint32x2, [u32; 3] => Uint32x3, [u32; 4] => Uint32x4,
+    i32 => Sint32, [i32; 2] => Sint32x2, [i32; 3] => Sint32x3, [i32; 4] => Sint32x4,
+    f32 => Float32, [f32; 2] => Float32x2, [f32; 3] => Float32x3, [f32; 4] => Float32x4,
+    f64 => Float64, [f64; 2] => Float64x2, [f64; 3] => Float64x3, [f64; 4] => Float64x4,
+
+    Unorm<u8> => Unorm8, Unorm<[u8; 2]> => Unorm8x2, Unorm<[u8; 4]> => Unorm8x4,
+    Snorm<i8> => Snorm8, Snorm<[i8; 2]> => Snorm8x2, Snorm<[i8; 4]> => Snorm8x4,
+    Unorm<u16> => Unorm16, Unorm<[u16; 2]> => Unorm16x2, Unorm<[u16; 4]> => Unorm16x4,
+    Snorm<i16> => Snorm16, Snorm<[i16; 2]> => Snorm16x2, Snorm<[i16; 4]> => Snorm16x4,
+
+    glam::Vec2 => Float32x2, glam::Vec3 => Float32x3, glam::Vec4 => Float32x4,
+    glam::UVec2 => Uint32x2, glam::UVec3 => Uint32x3, glam::UVec4 => Uint32x4,
+    glam::IVec2 => Sint32x2, glam::IVec3 => Sint32x3, glam::IVec4 => Sint32x4,
+    glam::DVec2 => Float64x2, glam::DVec3 => Float64x3, glam::DVec4 => Float64x4,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    #[derive(Pod, Zeroable, crate::VertexLayout)]
+    struct Vertex {
+        position: [f32; 3],
+        color: Unorm<[u8; 4]>,
+        #[location(4)]
+        uv: [f32; 2],
+        #[format(Float16x2)]
+        lightmap_uv: [u16; 2],
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    #[derive(Pod, Zeroable, crate::VertexLayout)]
+    #[step_mode(instance)]
+    struct Instance {
+        #[location(8)]
+        offset: [f32; 4],
+        scale: f32,
+    }
+
+    fn attributes<T: VertexArrayObject>() -> Vec<(wgpu::VertexFormat, u64, u32)> {
+        return T::VERTEX_BUFFER_LAYOUT.attributes.iter()
+            .map(|attribute| (attribute.format, attribute.offset, attribute.shader_location))
+        .collect();
+    }
+
+    #[test]
+    fn formats_follow_the_field_types() {
+        use wgpu::VertexFormat as F;
+        assert_eq!(attributes::<Vertex>(), vec![(F::Float32x3, 0, 0), (F::Unorm8x4, 12, 1), (F::Float32x2, 16, 4), (F::Float16x2, 24, 5)]);
+        assert_eq!(Vertex::VERTEX_BUFFER_LAYOUT.array_stride, 28);
+        assert_eq!(Vertex::VERTEX_BUFFER_LAYOUT.step_mode, wgpu::VertexStepMode::Vertex);
+    }
+
+    #[test]
+    fn instances() {
+        use wgpu::VertexFormat as F;
+        assert_eq!(attributes::<Instance>(), vec![(F::Float32x4, 0, 8), (F::Float32, 16, 9)]);
+        assert_eq!(Instance::VERTEX_BUFFER_LAYOUT.array_stride, 20);
+        assert_eq!(Instance::VERTEX_BUFFER_LAYOUT.step_mode, wgpu::VertexStepMode::Instance);
+    }
+}
@@ -1,14 +1,357 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
+use std::fmt::Write;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use naga::proc::Layouter;
+use naga::{ArraySize, Handle, Module, Scalar, Type, TypeInner, VectorSize};
+use gfx_preprocessor::{MemoryLayout, Preprocessor};
+
+pub use gfx_preprocessor::{ShaderDefines, ShaderError, ShaderRegistry};
+
+/// Generates Rust bindings for WGSL shaders, meant to be run from a build script
+///
+/// Each shader becomes a module named after its file containing a `#[repr(C)]`
+/// Pod struct for every struct the shader declares, `_GROUP`/`_BINDING`
+/// constants for every resource and the name of every entry point. The
+/// generated code refers to `glam` and `bytemuck`, so the including crate
+/// needs both, with glam's `bytemuck` feature enabled.
+#[derive(Debug, Default)]
+pub struct Bindings {
+    shaders: Vec<PathBuf>,
+    defines: ShaderDefines,
+    registry: ShaderRegistry,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn shader(mut self, path: impl Into<PathBuf>) -> Self {
+        self.shaders.push(path.into());
+        return self;
+    }
+
+    /// Defines every shader is preprocessed with
+    pub fn defines(mut self, defines: ShaderDefines) -> Self {
+        self.defines = defines;
+        return self;
+    }
+
+    /// Where `#import name::path` modules are looked up
+    pub fn registry(mut self, registry: ShaderRegistry) -> Self {
+        self.registry = registry;
+        return self;
+    }
+
+    pub fn generate(&self) -> Result<String, ShaderError> {
+        return Ok(self.generate_with_imports()?.0);
+    }
+
+    /// Write the bindings to `path` and tell cargo to rerun if any file that went into them changes
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), ShaderError> {
+        let (output, imports) = self.generate_with_imports()?;
+        for import in imports {
+            println!("cargo:rerun-if-changed={}", import.display());
+        }
+
+        return std::fs::write(path.as_ref(), output).map_err(|error| ShaderError::Io {
+            error: error,
+            imported_from: None,
+            path: path.as_ref().to_path_buf(),
+        });
+    }
+
+    fn generate_with_imports(&self) -> Result<(String, Vec<PathBuf>), ShaderError> {
+        let mut imports = Vec::new();
+        let mut output = String::from("// Generated from WGSL by the shader crate, do not edit\n");
+        for path in &self.shaders {
+            let shader = Preprocessor::with_defines(self.defines.clone())
+                .registry(self.registry.clone())
+            .process(path)?;
+            let (module, _) = shader.compile()?;
+            let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("shader");
+            output.push_str(&generate_module(&snake_case(name), &module));
+            imports.extend(shader.imports);
+        }
+        return Ok((output, imports));
+    }
+}
+
+/// Generate the bindings for a single module already parsed by naga
+pub fn generate_module(name: &str, module: &Module) -> String {
+    let mut output = String::new();
+    writeln!(output, "\n#[allow(dead_code)]\npub mod {} {{", identifier(name)).unwrap();
+
+    for entry in &module.entry_points {
+        writeln!(output, "    pub const {}: &str = \"{}\";", screaming_case(&entry.name), entry.name).unwrap();
+    }
+
+    for (_, variable) in module.global_variables.iter() {
+        let (Some(binding), Some(name)) = (&variable.binding, &variable.name) else {
+            continue;
+        };
+        let name = screaming_case(name);
+        writeln!(output, "    pub const {}_GROUP: u32 = {};", name, binding.group).unwrap();
+        writeln!(output, "    pub const {}_BINDING: u32 = {};", name, binding.binding).unwrap();
+    }
+
+    let mut layouter = Layouter::default();
+    if let Err(error) = layouter.update(module.to_ctx()) {
+        writeln!(output, "\n    // Skipped every struct, {}\n}}", error).unwrap();
+        return output;
+    }
+
+    // Types are in dependency order, so nested structs are always generated first
+    let mut generated = HashSet::new();
+    for (handle, ty) in module.types.iter() {
+        let (Some(name), TypeInner::Struct { members, span }) = (&ty.name, &ty.inner) else {
+            continue;
+        };
+        // Vertex inputs and outputs never live in a buffer
+        if members.iter().any(|member| member.binding.is_some()) {
+            continue;
+        }
+
+        match generate_struct(module, &layouter, &generated, name, members, *span) {
+            Ok(code) => {
+                output.push_str(&code);
+                generated.insert(handle);
+            }
+            Err(reason) => writeln!(output, "\n    // Skipped `{}`, {}", name, reason).unwrap(),
+        }
+    }
+
+    output.push_str("}\n");
+    return output;
+}
+
+fn generate_struct(module: &Module, layouter: &Layouter, generated: &HashSet<Handle<Type>>, name: &str, members: &[naga::StructMember], span: u32) -> Result<String, String> {
+    let field_name = |i: usize, member: &naga::StructMember| identifier(&snake_case(member.name.as_deref().unwrap_or(&format!("field{}", i))));
+
+    // A runtime sized array can only be the last member, the struct describes everything before it
+    let mut runtime_array = None;
+    let mut sized = members;
+    if let Some((last, rest)) = members.split_last()
+        && let TypeInner::Array { base, size: ArraySize::Dynamic, .. } = module.types[last.ty].inner {
+        runtime_array = Some(format!("followed by `{}`, a runtime sized array of `{}`", field_name(rest.len(), last), rust_type(module, generated, base)?));
+        sized = rest;
+    }
+
+    // The same placement `#[derive(GpuLayout)]` uses, WGSL lays out every struct like std430
+    let placed = sized.iter().map(|member| {
+        let layout = layouter[member.ty];
+        // naga's `Alignment` has no getter, multiplying is the only way to get a number out of it
+        return ((layout.alignment * 1) as usize, layout.size as usize);
+    }).collect::<Vec<_>>();
+    let mut offsets = vec![0; sized.len()];
+    let (_, size) = MemoryLayout::Std430.place(&placed, &mut offsets);
+    // naga's offsets already include any `@align` and `@size`, which the layout doesn't know about
+    let moved = sized.iter().zip(&offsets).any(|(member, offset)| member.offset as usize != *offset);
+    if moved || (runtime_array.is_none() && span as usize != size) {
+        return Err(String::from("`@align` and `@size` are not supported"));
+    }
+
+    let mut fields = String::new();
+    let mut asserts = String::new();
+    let mut end = 0;
+    let mut padding = 0;
+    for (i, member) in sized.iter().enumerate() {
+        let field = field_name(i, member);
+        if offsets[i] > end {
+            writeln!(fields, "        pub _padding{}: {},", padding, padding_type(offsets[i] - end)).unwrap();
+            padding += 1;
+        }
+        writeln!(fields, "        pub {}: {},", field, rust_type(module, generated, member.ty)?).unwrap();
+        writeln!(asserts, "    const _: () = assert!(core::mem::offset_of!({}, {}) == {});", name, field, offsets[i]).unwrap();
+        end = offsets[i] + placed[i].1;
+    }
+    // WGSL rounds structs up to their alignment, arrays of them rely on it
+    if runtime_array.is_none() && size > end {
+        writeln!(fields, "        pub _padding{}: {},", padding, padding_type(size - end)).unwrap();
+        end = size;
+    }
+
+    let mut output = String::from("\n");
+    if let Some(runtime_array) = runtime_array {
+        writeln!(output, "    /// In the shader this is {}", runtime_array).unwrap();
+    }
+    output.push_str("    #[repr(C)]\n");
+    output.push_str("    #[derive(Debug, Clone, Copy, PartialEq)]\n");
+    output.push_str("    #[derive(bytemuck::Pod, bytemuck::Zeroable)]\n");
+    writeln!(output, "    pub struct {} {{\n{}    }}", name, fields).unwrap();
+    writeln!(output, "    const _: () = assert!(core::mem::size_of::<{}>() == {});", name, end).unwrap();
+    output.push_str(&asserts);
+    return Ok(output);
+}
+
+fn rust_type(module: &Module, generated: &HashSet<Handle<Type>>, ty: Handle<Type>) -> Result<String, String> {
+    return match module.types[ty].inner {
+        TypeInner::Scalar(scalar) | TypeInner::Atomic(scalar) => scalar_type(scalar).map(String::from),
+        TypeInner::Vector { size, scalar } => {
+            let prefix = match scalar {
+                Scalar::F32 => "",
+                Scalar::U32 => "U",
+                Scalar::I32 => "I",
+                _ => return Err(format!("{:?} vectors are not supported", scalar)),
+            };
+            Ok(format!("glam::{}Vec{}", prefix, size as u8))
+        }
+        TypeInner::Matrix { columns: VectorSize::Quad, rows: VectorSize::Quad, scalar: Scalar::F32 } => {
+            Ok(String::from("glam::Mat4"))
+        }
+        // glam's other matrices don't share WGSL's column padding or alignment
+        TypeInner::Matrix { columns, rows, scalar: Scalar::F32 } => {
+            let rows = match rows {
+                VectorSize::Tri => 4,
+                rows => rows as u8,
+            };
+            Ok(format!("[[f32; {}]; {}]", rows, columns as u8))
+        }
+        TypeInner::Array { base, size: ArraySize::Constant(size), stride } => {
+            let element = module.types[base].inner.size(module.to_ctx());
+            if element != stride {
+                return Err(format!("an array has a stride of {} but {} byte elements", stride, element));
+            }
+            Ok(format!("[{}; {}]", rust_type(module, generated, base)?, size))
+        }
+        TypeInner::Struct { .. } if generated.contains(&ty) => {
+            Ok(module.types[ty].name.clone().unwrap())
+        }
+        ref inner => Err(format!("{:?} has no host layout", inner)),
+    };
+}
+
+fn scalar_type(scalar: Scalar) -> Result<&'static str, String> {
+    return match scalar {
+        Scalar::F32 => Ok("f32"),
+        Scalar::U32 => Ok("u32"),
+        Scalar::I32 => Ok("i32"),
+        _ => Err(format!("{:?} is not supported", scalar)),
+    };
+}
+
+fn padding_type(bytes: usize) -> String {
+    return match bytes % 4 {
+        0 => format!("[u32; {}]", bytes / 4),
+        _ => format!("[u8; {}]", bytes),
+    };
+}
+
+/// `uRandSeed` to `u_rand_seed`
+fn snake_case(name: &str) -> String {
+    let mut output = String::with_capacity(name.len());
+    let mut previous = None::<char>;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit()) {
+            output.push('_');
+        }
+        output.push(c.to_ascii_lowercase());
+        previous = Some(c);
+    }
+    return output;
+}
+
+fn screaming_case(name: &str) -> String {
+    return snake_case(name).to_ascii_uppercase();
+}
+
+fn identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "break", "const", "continue", "else", "enum", "extern", "false", "fn", "for",
+        "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+        "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while", "async",
+        "await", "dyn", "abstract", "become", "box", "do", "final", "macro", "override", "priv",
+        "typeof", "unsized", "virtual", "yield", "try", "gen",
+    ];
+    return match KEYWORDS.contains(&name) {
+        true => format!("r#{}", name),
+        false => name.to_string(),
+    };
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn generate(source: &str) -> String {
+        let module = naga::front::wgsl::parse_str(source).unwrap();
+        return generate_module("test", &module);
+    }
+
+    #[test]
+    fn scalar_packs_after_vec3() {
+        let output = generate("
+            struct Light { color: vec3<f32>, intensity: f32, position: vec3<f32> }
+            @group(0) @binding(7) var<uniform> uLight: Light;
+        ");
+        assert!(output.contains("pub color: glam::Vec3,\n        pub intensity: f32,\n        pub position: glam::Vec3,\n        pub _padding0: [u32; 1],\n"), "{}", output);
+        assert!(output.contains("assert!(core::mem::offset_of!(Light, intensity) == 12);"), "{}", output);
+        assert!(output.contains("assert!(core::mem::offset_of!(Light, position) == 16);"), "{}", output);
+        assert!(output.contains("assert!(core::mem::size_of::<Light>() == 32);"), "{}", output);
+    }
+
+    #[test]
+    fn pads_up_to_member_alignment() {
+        let output = generate("
+            struct Camera { position: vec3<f32>, view: mat4x4<f32>, normal: mat3x3<f32> }
+        ");
+        assert!(output.contains("pub position: glam::Vec3,\n        pub _padding0: [u32; 1],\n        pub view: glam::Mat4,\n"), "{}", output);
+        assert!(output.contains("pub normal: [[f32; 4]; 3],\n"), "{}", output);
+        assert!(output.contains("assert!(core::mem::offset_of!(Camera, normal) == 80);"), "{}", output);
+        assert!(output.contains("assert!(core::mem::size_of::<Camera>() == 128);"), "{}", output);
+    }
+
+    #[test]
+    fn nested_structs_and_arrays() {
+        let output = generate("
+            struct Inner { offset: vec2<f32> }
+            struct Outer { scale: f32, inner: Inner, colors: array<vec4<f32>, 2> }
+        ");
+        assert!(output.contains("pub struct Inner {\n        pub offset: glam::Vec2,\n    }"), "{}", output);
+        assert!(output.contains("pub scale: f32,\n        pub _padding0: [u32; 1],\n        pub inner: Inner,\n        pub colors: [glam::Vec4; 2],\n"), "{}", output);
+        assert!(output.contains("assert!(core::mem::offset_of!(Outer, colors) == 16);"), "{}", output);
+        assert!(output.contains("assert!(core::mem::size_of::<Outer>() == 48);"), "{}", output);
+    }
+
+    #[test]
+    fn skips_what_has_no_host_layout() {
+        let output = generate("
+            struct VertexInput { @location(0) position: vec3<f32> }
+            struct Flags { mask: vec2<bool> }
+            struct Particles { count: u32, positions: array<vec4<f32>> }
+        ");
+        assert!(!output.contains("struct VertexInput"), "{}", output);
+        assert!(output.contains("// Skipped `Flags`"), "{}", output);
+        assert!(output.contains("/// In the shader this is followed by `positions`, a runtime sized array of `glam::Vec4`"), "{}", output);
+        assert!(output.contains("assert!(core::mem::size_of::<Particles>() == 4);"), "{}", output);
+    }
+
+    #[test]
+    fn skips_explicit_layouts() {
+        let output = generate("
+            struct Aligned { scale: f32, @align(16) offset: vec2<f32> }
+            struct Sized { @size(16) scale: f32 }
+        ");
+        assert!(output.contains("// Skipped `Aligned`, `@align` and `@size` are not supported"), "{}", output);
+        assert!(output.contains("// Skipped `Sized`, `@align` and `@size` are not supported"), "{}", output);
+    }
+
+    #[test]
+    fn binding_and_entry_point_constants() {
+        let output = generate("
+            @group(1) @binding(2) var<uniform> uRandSeed: u32;
+            @compute @workgroup_size(1) fn cs_main() { _ = uRandSeed; }
+        ");
+        assert!(output.contains("pub const U_RAND_SEED_GROUP: u32 = 1;"), "{}", output);
+        assert!(output.contains("pub const U_RAND_SEED_BINDING: u32 = 2;"), "{}", output);
+        assert!(output.contains("pub const CS_MAIN: &str = \"cs_main\";"), "{}", output);
+    }
+
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn names() {
+        assert_eq!(snake_case("uRandSeed"), "u_rand_seed");
+        assert_eq!(snake_case("view2D"), "view2_d");
+        assert_eq!(screaming_case("tAlbedo"), "T_ALBEDO");
+        assert_eq!(identifier("type"), "r#type");
     }
 }
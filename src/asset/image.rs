@@ -38,11 +38,11 @@ impl Image {
 
         let mut levels = Vec::new();
         log::debug!("Generating mipmaps");
-        // FIXME: sometimes data.len() != width * height * 4
+        // Exactly the size of each mip, even when halving doesn't keep the aspect ratio
         for level in 0..effective_mip_levels {
-            image = image.resize(
-                width >> level,
-                height >> level,
+            image = image.resize_exact(
+                (width >> level).max(1),
+                (height >> level).max(1),
                 image::imageops::FilterType::Triangle,
             );
             levels.push(image.to_rgba8().to_vec());
@@ -97,6 +97,19 @@ impl Image {
         return image;
     }
 
+    /// Upload every mip level to a new 2D texture, `format` has to take the RGBA8 data as is
+    ///
+    /// Levels past what the texture's size has room for are left out.
+    pub fn to_texture(&self, device: &gfx_ne::Device, label: Option<String>, format: wgpu::TextureFormat) -> gfx_ne::Texture {
+        let mode = gfx_ne::TextureMode {
+            mip_levels: self.mip_levels,
+            ..gfx_ne::TextureMode::new(gfx_ne::TextureKind::D2, format)
+        };
+        let texture = gfx_ne::Texture::new(device, label, mode, self.width, self.height);
+        texture.write_mips(0, &self.data[..texture.mode.mip_levels as usize]);
+        return texture;
+    }
+
     pub fn empty() -> Self {
         Self {
             width: 1,
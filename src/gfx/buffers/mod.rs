@@ -1,7 +1,5 @@
-mod index;
-mod vertex;
 mod uniform;
 
-pub use index::IndexBuffer;
-pub use uniform::{UniformBuffer, Uniform};
-pub use vertex::{VertexBuffer, VertexArrayObject};
+pub use gfx_ne::UniformArena;
+pub use uniform::UniformBuffer;
+pub use gfx_ne::VertexArrayObject;
@@ -28,7 +28,8 @@ impl<T: Uniform> UniformBuffer<T> {
             label: Some(name),
             size: padded_size,
             mapped_at_creation: true,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM
+            // COPY_SRC so the contents can be read back for debugging
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::UNIFORM
         });
         // no bounds checking on `get_mapped_range_mut` is a bit scary
         buffer.slice(..).get_mapped_range_mut().copy_from_slice(bytemuck::cast_slice(&[data]));
@@ -60,9 +61,29 @@ impl<T: Uniform> UniformBuffer<T> {
         }
     }
 
+    /// Like `update` but batched with every other upload of the frame, sent when `uploads` is flushed
+    pub fn upload(&mut self, uploads: &mut gfx_ne::StagingBelt) {
+        if self.updated == true {
+            self.updated = false;
+            uploads.write(&self.buffer, 0, bytemuck::cast_slice(&[self.data]));
+        }
+    }
+
     pub fn as_entire_binding(&self) -> wgpu::BindingResource<'_> {
         return self.buffer.as_entire_binding();
     }
+
+    /// What the GPU actually has, which lags behind `data` until the next `update`
+    pub async fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<T, gfx_ne::ReadbackError> {
+        let bytes = gfx_ne::read_buffer(device, queue, &self.buffer, size_of::<T>()).await?;
+        return Ok(bytemuck::pod_read_unaligned(&bytes[..size_of::<T>()]));
+    }
+
+    /// `read`, blocking until the data is back, not on the web where that never happens
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_blocking(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<T, gfx_ne::ReadbackError> {
+        return futures_lite::future::block_on(self.read(device, queue));
+    }
 }
 
 impl<T: Uniform> Deref for UniformBuffer<T> {
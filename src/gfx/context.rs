@@ -4,7 +4,9 @@ use std::path::PathBuf;
 use wgpu::SurfaceTargetUnsafe;
 
 use window::Window;
+use crate::rend::Vertex;
 use crate::asset::Image;
+use gfx_ne::{GeometryPool, ShaderConstants, ShaderDefines, ShaderWatcher};
 use std::collections::HashMap;
 use super::{RenderTexture, Texture};
 use super::{RenderTargetFormat, TextureFormat};
@@ -19,10 +21,18 @@ pub struct RenderContext {
     pub _instance: wgpu::Instance,
     pub surface: wgpu::Surface<'static>,
     pub config: wgpu::SurfaceConfiguration,
+    /// The same handles as above, for creating gfx-ne objects
+    pub gpu: gfx_ne::Device,
+    /// Uniform writes for the frame, flushed once before rendering
+    pub uploads: gfx_ne::StagingBelt,
+    /// Every mesh's vertices and indices, bound once and drawn with `base_vertex`
+    pub geometry: GeometryPool<Vertex, u32>,
 
-    pub shaders: HashMap<PathBuf, Arc<Shader>>,
+    pub shaders: HashMap<(PathBuf, ShaderDefines), Arc<Shader>>,
+    pub shader_watcher: Option<ShaderWatcher<(PathBuf, ShaderDefines)>>,
     pub samplers: HashMap<SamplerMode, Arc<Sampler>>,
-    pub pipeline_layouts: HashMap<String, Arc<wgpu::PipelineLayout>>,
+    pub pipeline_layouts: HashMap<(String, Vec<Vec<wgpu::BindGroupLayoutEntry>>), Arc<wgpu::PipelineLayout>>,
+    pub render_pipelines: HashMap<(String, String, ShaderConstants), Arc<wgpu::RenderPipeline>>,
     pub bindgroup_layouts: HashMap<Vec<wgpu::BindGroupLayoutEntry>, Arc<wgpu::BindGroupLayout>>,
 }
 
@@ -91,7 +101,17 @@ impl RenderContext {
         };
         surface.configure(&device, &config);
 
+        Shader::register_library();
+        let gpu = gfx_ne::Device {
+            queue: queue.clone(),
+            device: device.clone(),
+            adapter: adapter.clone(),
+            instance: instance.clone(),
+        };
         return Self {
+            uploads: gfx_ne::StagingBelt::new(&device, 64 * 1024),
+            geometry: GeometryPool::new(&gpu, Some("Mesh Geometry"), 1 << 16, 1 << 18),
+            gpu: gpu,
             queue: queue,
             device: device,
             config: config,
@@ -99,8 +119,12 @@ impl RenderContext {
             _adapter: adapter,
             _instance: instance,
             shaders: HashMap::new(),
+            shader_watcher: ShaderWatcher::new().inspect_err(|error| {
+                log::warn!("Shader hot reloading is disabled: {}", error);
+            }).ok(),
             samplers: HashMap::new(),
             pipeline_layouts: HashMap::new(),
+            render_pipelines: HashMap::new(),
             bindgroup_layouts: HashMap::new(),
         };
     }
@@ -110,15 +134,57 @@ impl RenderContext {
     }
 
     pub fn create_shader(&mut self, path: impl Into<PathBuf>) -> Arc<Shader> {
-        let path = path.into();
-        return self.shaders.get(&path).cloned().unwrap_or_else(|| {
-            log::info!("Creating shader from {:?}", path);
-            let shader = Arc::new(Shader::new(&self.device, None, &path));
-            self.shaders.insert(path.clone(), shader.clone());
+        return self.create_shader_with_defines(path, ShaderDefines::new());
+    }
+
+    /// Each permutation of defines is compiled once and then shared
+    ///
+    /// A shader that fails to load is logged and drawn with `error.wgsl` until it reloads.
+    pub fn create_shader_with_defines(&mut self, path: impl Into<PathBuf>, defines: ShaderDefines) -> Arc<Shader> {
+        let key = (path.into(), defines);
+        return self.shaders.get(&key).cloned().unwrap_or_else(|| {
+            log::info!("Creating shader from {:?} [{}]", key.0, key.1);
+            let shader = Arc::new(Shader::with_defines(&self.gpu, None, &key.0, key.1.clone()).unwrap_or_else(|error| {
+                log::error!("Failed to create shader from {:?}, using error.wgsl until it is fixed\n{}", key.0, error);
+                Shader::fallback(&self.gpu, None, &key.0, key.1.clone())
+            }));
+            if let Some(watcher) = &mut self.shader_watcher {
+                watcher.watch(key.clone(), &shader.imports.read().unwrap());
+            }
+            self.shaders.insert(key, shader.clone());
             return shader;
         });
     }
 
+    /// Recompile every shader with a file that changed on disk, returns how many were reloaded
+    ///
+    /// Shaders that fail to compile keep their last good module and generation.
+    pub fn reload_changed_shaders(&mut self) -> usize {
+        let Some(watcher) = &mut self.shader_watcher else {
+            return 0;
+        };
+
+        let mut reloaded = 0;
+        for key in watcher.changed() {
+            let Some(shader) = self.shaders.get(&key) else {
+                watcher.unwatch(&key);
+                continue;
+            };
+            match shader.reload(&self.gpu) {
+                Ok(()) => {
+                    let layout_key = Self::pipeline_layout_key(shader);
+                    self.render_pipelines.retain(|(_, shader_key, _), _| *shader_key != layout_key);
+                    self.pipeline_layouts.retain(|(shader_key, _), _| *shader_key != layout_key);
+                    reloaded += 1;
+                }
+                Err(error) => log::error!("Failed to reload {}\n{}", shader.name, error),
+            }
+            // Imports may have changed even if compiling failed
+            watcher.watch(key, &shader.imports.read().unwrap());
+        }
+        return reloaded;
+    }
+
     pub fn create_texture(&mut self, name: Option<&str>, mode: SamplerMode, format: TextureFormat, image: Arc<Image>) -> Texture {
         let sampler = self.create_sampler(name, mode);
         return Texture::new(&self.device, &self.queue, name, format, sampler, image);
@@ -152,9 +218,83 @@ impl RenderContext {
         });
     }
 
+    /// The layout of `group` as declared by the shader, shared with any other layout with the same entries
+    pub fn create_shader_bind_group_layout(&mut self, shader: &Shader, group: u32) -> Arc<wgpu::BindGroupLayout> {
+        let label = format!("{} Group {} Layout", shader.name, group);
+        let entries = shader.reflection().layout_entries(group);
+        return self.create_bind_group_layout(wgpu::BindGroupLayoutDescriptor {
+            label: Some(&label),
+            entries: &entries,
+        });
+    }
+
+    /// A layout made from `groups`, in order, with reflection filling in any groups past them
+    ///
+    /// `groups` should be the layouts of the bind groups the pipeline is drawn with,
+    /// they are checked against the shader and nothing is created if one doesn't match.
+    pub fn create_pipeline_layout(&mut self, shader: &Shader, groups: &[&wgpu::BindGroupLayoutDescriptor]) -> Result<Arc<wgpu::PipelineLayout>, String> {
+        let key = (Self::pipeline_layout_key(shader), groups.iter().map(|group| group.entries.to_vec()).collect::<Vec<_>>());
+        if let Some(layout) = self.pipeline_layouts.get(&key) {
+            return Ok(layout.clone());
+        }
+
+        log::info!("Creating pipeline layout for {}", key.0);
+        let reflection = shader.reflection();
+        for (group, descriptor) in groups.iter().enumerate() {
+            if let Err(error) = reflection.check_layout(group as u32, descriptor.entries) {
+                return Err(format!("{} doesn't match the layout of group {} {:?}: {}", shader.name, group, descriptor.label, error));
+            }
+        }
+        let count = (groups.len() as u32).max(reflection.group_count());
+        drop(reflection);
+        let layouts = (0..count).map(|group| match groups.get(group as usize) {
+            Some(descriptor) => self.create_bind_group_layout((*descriptor).clone()),
+            None => self.create_shader_bind_group_layout(shader, group),
+        }).collect::<Vec<_>>();
+        let layout = Arc::new(self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            push_constant_ranges: &[],
+            label: Some(&format!("{} Pipeline Layout", shader.name)),
+            bind_group_layouts: &layouts.iter().map(|group| group.as_ref()).collect::<Vec<_>>(),
+        }));
+        self.pipeline_layouts.insert(key, layout.clone());
+        return Ok(layout);
+    }
+
+    /// Each pipeline is built once per shader permutation and set of constants, then shared
+    ///
+    /// `build` is only called on a miss and is given the compilation options carrying
+    /// the constants, which should be used for every stage. Constants the shader
+    /// doesn't declare are dropped. Errors from `build` are passed on and not cached.
+    pub fn create_render_pipeline(&mut self, label: &str, shader: &Shader, constants: &ShaderConstants, build: impl FnOnce(&mut Self, wgpu::PipelineCompilationOptions) -> Result<wgpu::RenderPipeline, String>) -> Result<Arc<wgpu::RenderPipeline>, String> {
+        let constants = constants.declared_by(&shader.reflection());
+        let key = (label.to_string(), Self::pipeline_layout_key(shader), constants);
+        if let Some(pipeline) = self.render_pipelines.get(&key) {
+            return Ok(pipeline.clone());
+        }
+
+        log::info!("Creating render pipeline {} for {} [{}]", key.0, key.1, key.2);
+        let values = key.2.iter().collect::<Vec<_>>();
+        let pipeline = Arc::new(build(self, wgpu::PipelineCompilationOptions {
+            constants: &values,
+            ..Default::default()
+        })?);
+        self.render_pipelines.insert(key, pipeline.clone());
+        return Ok(pipeline);
+    }
+
+    /// Drop the pipeline made for `constants`, e.g. when they changed and won't be used again
+    pub fn remove_render_pipeline(&mut self, label: &str, shader: &Shader, constants: &ShaderConstants) -> Option<Arc<wgpu::RenderPipeline>> {
+        let key = (label.to_string(), Self::pipeline_layout_key(shader), constants.declared_by(&shader.reflection()));
+        return self.render_pipelines.remove(&key);
+    }
+
     pub fn create_bind_group<T: BindGroupState>(&mut self, initial_state: Option<T>) -> BindGroup<T> {
         let state = initial_state.unwrap_or_else(|| T::init(&self.device));
         let layout = self.create_bind_group_layout(state.get_layout_descriptor());
         return BindGroup::new(&self.device, Some(state.get_name()), state, layout);
     }
+
+    fn pipeline_layout_key(shader: &Shader) -> String {
+        return format!("{} [{}]", shader.src_path.display(), shader.defines);
+    }
 }
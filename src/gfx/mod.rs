@@ -8,5 +8,6 @@ pub use buffers::*;
 pub use objects::*;
 pub use textures::*;
 pub use context::RenderContext;
+pub use gfx_ne::ShaderConstants;
 pub use format::{RenderTargetFormat, TextureFormat};
 
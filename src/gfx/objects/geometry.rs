@@ -1,35 +1,41 @@
-use wgpu::Device;
 use crate::rend::Vertex;
-use crate::gfx::{VertexBuffer, IndexBuffer};
+use gfx_ne::{GeometryAllocation, GeometryPool};
 type Primitive = (&'static [Vertex], &'static [u32]);
 
+/// A mesh's vertices and indices, living in a shared `GeometryPool`
 pub struct Geometry {
     pub name: String,
     pub indices: Vec<u32>,
     pub vertices: Vec<Vertex>,
-    pub index_buffer: IndexBuffer,
-    pub vertex_buffer: VertexBuffer<Vertex>,
+    pub allocation: GeometryAllocation,
 }
 
 impl Geometry {
     // TODO: Make vertex buffer input generic
-    pub fn new(device: &Device, name: Option<&str>, vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+    pub fn new(pool: &mut GeometryPool<Vertex, u32>, name: Option<&str>, vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
         let name = name.unwrap_or("Unnamed Geometry");
-
-        let index_buffer = IndexBuffer::new(device, Some(format!("{} Index Buffer", name).as_str()), indices.clone());
-        let vertex_buffer = VertexBuffer::new(device, Some(format!("{} Vertex Buffer", name).as_str()), vertices.clone());
+        let allocation = pool.allocate(&vertices, &indices);
 
         return Geometry {
             name: name.to_string(),
             indices,
             vertices,
-            index_buffer,
-            vertex_buffer,
+            allocation,
         }
     }
 
+    /// The indices to draw from the pool, used together with `base_vertex`
     pub fn range(&self) -> std::ops::Range<u32> {
-        0..self.indices.len() as u32
+        return self.allocation.draw_range();
+    }
+
+    pub fn base_vertex(&self) -> i32 {
+        return self.allocation.base_vertex();
+    }
+
+    /// Give the space back to `pool` so other geometry can reuse it
+    pub fn free(self, pool: &mut GeometryPool<Vertex, u32>) {
+        pool.free(self.allocation);
     }
 }
 
@@ -39,15 +45,16 @@ impl std::fmt::Debug for Geometry {
             .field("name", &self.name)
             .field("indices", &self.indices.len())
             .field("vertices", &self.vertices.len())
+            .field("allocation", &self.allocation)
         .finish()
     }
 }
 
 impl Geometry {
-    pub fn from_primitive(device: &Device, name: Option<&str>, primitive: &Primitive) -> Self {
+    pub fn from_primitive(pool: &mut GeometryPool<Vertex, u32>, name: Option<&str>, primitive: &Primitive) -> Self {
         let vertices = primitive.0.to_vec();
         let indices = primitive.1.to_vec();
-        return Self::new(device, name, vertices, indices);
+        return Self::new(pool, name, vertices, indices);
     }
 
     #[allow(dead_code)]
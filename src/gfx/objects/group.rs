@@ -1,24 +1,14 @@
 use std::sync::Arc;
+use gfx_ne::BindGroupState;
 use std::{fmt::Debug, ops::{Deref, DerefMut}};
 
-pub trait BindGroupState: Sized {
-    /// The name of the bind group
-    fn get_name(&self) -> &'static str;
-    /// Default initialization of the state
-    fn init(device: &wgpu::Device) -> Self;
-    /// Update all of the bound buffers attached to the bind group
-    fn update(&mut self, queue: &wgpu::Queue);
-    /// The layout which describes how data is bound in the bind group
-    fn get_layout_descriptor(&self) -> wgpu::BindGroupLayoutDescriptor;
-    /// Create a new bind group adhering to the layout defined in `get_layout_descriptor`
-    fn create_bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup;
-}
-
 pub struct BindGroup<T: BindGroupState> {
     pub name: String,
     pub state: T,
     pub bind_group: wgpu::BindGroup,
     pub layout: Arc<wgpu::BindGroupLayout>,
+    /// The state's generation when `bind_group` was created
+    generation: u64,
 }
 
 #[profiling::all_functions]
@@ -27,6 +17,7 @@ impl<T: BindGroupState> BindGroup<T> {
         let name = name.unwrap_or("Unnamed BindGroup").to_string();
         let bind_group = state.create_bind_group(&device, &layout);
         return BindGroup {
+            generation: state.generation(),
             name,
             state,
             bind_group,
@@ -37,11 +28,21 @@ impl<T: BindGroupState> BindGroup<T> {
     /// Recreate the bind group with the current state
     pub fn recreate(&mut self, device: &wgpu::Device) {
         self.bind_group = self.state.create_bind_group(&device, &self.layout);
+        self.generation = self.state.generation();
+    }
+
+    /// Recreate the bind group only if one of its buffers was reallocated, returns whether it was
+    pub fn refresh(&mut self, device: &wgpu::Device) -> bool {
+        let stale = self.state.generation() != self.generation;
+        if stale {
+            self.recreate(device);
+        }
+        return stale;
     }
 
     /// Update all of the bound buffers attached to the bind group
-    pub fn update(&mut self, queue: &wgpu::Queue) {
-        self.state.update(queue);
+    pub fn update(&mut self, uploads: &mut gfx_ne::StagingBelt) {
+        self.state.update(uploads);
     }
 
     /// Get the raw underlying bind group
@@ -5,5 +5,6 @@ mod geometry;
 
 pub use geometry::Geometry;
 pub use shader::{Shader, ShaderStage};
-pub use group::{BindGroup, BindGroupState};
+pub use group::BindGroup;
+pub use gfx_ne::BindGroupState;
 pub use pipeline::{RenderPipeline, PipelineDescriptor};
@@ -1,7 +1,8 @@
-use std::fs;
 use std::fmt::Debug;
+use gfx_ne::{Device, Preprocessor, Reflection, ShaderDefines, ShaderError, ShaderRegistry};
 use std::sync::LazyLock;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{RwLock, RwLockReadGuard};
 
 static SHADER_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
@@ -13,81 +14,111 @@ static SHADER_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
     return path;
 });
 
-#[derive(Debug, Clone)]
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-pub enum ShaderStage {
-    Vertex,
-    Compute,
-    Fragment
-}
+pub use gfx_ne::ShaderStage;
 
 pub struct Shader {
     pub name: String,
     pub src_path: PathBuf,
+    pub defines: ShaderDefines,
+    pub imports: RwLock<Vec<PathBuf>>,
+    pub generation: AtomicU64,
+    pub reflection: RwLock<Reflection>,
     pub module: RwLock<wgpu::ShaderModule>,
-    pub stages: Vec<(ShaderStage, String)>,
 }
 
 impl Shader {
-    pub fn new(device: &wgpu::Device, name: Option<&str>, source_path: impl Into<PathBuf>) -> Self {
+    /// Make the shared shader types importable by name, e.g. `#import pregen::camera`
+    pub fn register_library() {
+        let roots = SHADER_PATH.join("shaders/roots");
+        if let Err(error) = ShaderRegistry::global().write().unwrap().add_roots_from(&roots) {
+            log::error!("Failed to load shader module roots: {}", error);
+        }
+    }
+
+    pub fn new(device: &Device, name: Option<&str>, source_path: impl Into<PathBuf>) -> Result<Self, ShaderError> {
+        return Self::with_defines(device, name, source_path, ShaderDefines::new());
+    }
+
+    pub fn with_defines(device: &Device, name: Option<&str>, source_path: impl Into<PathBuf>, defines: ShaderDefines) -> Result<Self, ShaderError> {
         let path = Path::new(&*SHADER_PATH).join(source_path.into());
-        let source = fs::read_to_string(&path).unwrap();
-        let stages = Self::parse_wgsl(&source);
+        let shader = Preprocessor::with_defines(defines.clone()).process(&path)?;
+        let (module, info) = shader.compile()?;
+        let reflection = Reflection::new(&module, &info);
 
-        let name = name.unwrap_or(path.file_stem().unwrap().to_str().unwrap());
-        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some(name),
-            source: wgpu::ShaderSource::Wgsl(source.into()),
+        let name = Self::label(name, &path, &defines);
+        let module = gfx_ne::create_module(device, &name, Some(&shader.map), wgpu::ShaderSource::Wgsl(shader.source.into()))?;
+
+        return Ok(Self {
+            name: name,
+            defines: defines,
+            module: RwLock::new(module),
+            imports: RwLock::new(shader.imports),
+            src_path: path,
+            generation: AtomicU64::new(0),
+            reflection: RwLock::new(reflection),
         });
+    }
+
+    /// Stand in for a shader that failed to load, drawing `error.wgsl` until it reloads successfully
+    ///
+    /// Reloading still compiles `source_path`, so fixing the file brings the real shader back.
+    pub fn fallback(device: &Device, name: Option<&str>, source_path: impl Into<PathBuf>, defines: ShaderDefines) -> Self {
+        let path = Path::new(&*SHADER_PATH).join(source_path.into());
+        let fallback = Self::new(device, name, "shaders/error.wgsl")
+            .unwrap_or_else(|error| panic!("The fallback shader failed to compile: {}", error));
 
+        // If only compiling failed we still know every file the shader is made of
+        let imports = Preprocessor::with_defines(defines.clone()).process(&path)
+            .map_or_else(|_| vec![path.clone()], |shader| shader.imports);
         return Self {
-            name: name.to_string(),
-            module: RwLock::new(module),
-            stages: stages,
+            name: Self::label(name, &path, &defines),
+            imports: RwLock::new(imports),
+            defines: defines,
             src_path: path,
+            ..fallback
         };
     }
 
-    pub fn get_entry(&self, stage: ShaderStage) -> Option<&str> {
-        for (s, entry) in &self.stages {
-            if s == &stage {
-                return Some(entry.as_str());
-            }
-        }
-        return None;
+    pub fn get_entry(&self, stage: ShaderStage) -> Option<String> {
+        return self.reflection().entry_point(stage).map(|entry| entry.name.clone());
     }
 
-    pub fn as_raw(&self) -> RwLockReadGuard<wgpu::ShaderModule> {
+    pub fn reflection(&self) -> RwLockReadGuard<'_, Reflection> {
+        return self.reflection.read().unwrap();
+    }
+
+    /// Changes every time the shader is reloaded, pipelines should be rebuilt when it does
+    pub fn generation(&self) -> u64 {
+        return self.generation.load(Ordering::Acquire);
+    }
+
+    pub fn as_raw(&self) -> RwLockReadGuard<'_, wgpu::ShaderModule> {
         return self.module.read().unwrap();
     }
 
-    pub fn reload(&self, device: &wgpu::Device) {
-        let source = fs::read_to_string(&self.src_path).unwrap();
-        let mut module = self.module.write().unwrap();
-        *module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some(&self.name),
-            source: wgpu::ShaderSource::Wgsl(source.into()),
-        });
+    /// Recompile the shader, if it fails the last good module is kept
+    pub fn reload(&self, device: &Device) -> Result<(), ShaderError> {
+        let shader = Preprocessor::with_defines(self.defines.clone()).process(&self.src_path)?;
+        // Keep watching any new imports even if they don't compile yet
+        *self.imports.write().unwrap() = shader.imports.clone();
+        let (module, info) = shader.compile()?;
+        // naga can accept what the backend still rejects, nothing is replaced until both pass
+        let raw = gfx_ne::create_module(device, &self.name, Some(&shader.map), wgpu::ShaderSource::Wgsl(shader.source.into()))?;
+
+        *self.reflection.write().unwrap() = Reflection::new(&module, &info);
+        *self.module.write().unwrap() = raw;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        log::info!("Reloaded shader {}", self.name);
+        return Ok(());
     }
 
-    fn parse_wgsl(source: &str) -> Vec<(ShaderStage, String)> {
-        let mut stages = Vec::new();
-        for (i, line) in source.lines().enumerate() {
-            if line.starts_with("@") {
-                let stage = match &line.to_lowercase()[..] {
-                    "@vertex" => ShaderStage::Vertex,
-                    "@compute" => ShaderStage::Compute,
-                    "@fragment" => ShaderStage::Fragment,
-                    _ => continue,
-                };
-                let entry_point = source.lines().nth(i + 1).unwrap().trim();
-                let fn_index = entry_point.find("fn").unwrap() + 3;
-                let open_paren_index = entry_point.find("(").unwrap();
-                let name = entry_point[fn_index..open_paren_index].trim();
-                stages.push((stage, name.to_string()));
-            }
-        }
-        return stages;
+    fn label(name: Option<&str>, path: &Path, defines: &ShaderDefines) -> String {
+        let stem = path.file_stem().unwrap().to_str().unwrap();
+        return match name {
+            Some(name) => name.to_string(),
+            None if defines.is_empty() => stem.to_string(),
+            None => format!("{} [{}]", stem, defines),
+        };
     }
 }
 
@@ -95,8 +126,11 @@ impl Debug for Shader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Shader")
             .field("name", &self.name)
-            .field("stages", &self.stages)
+            .field("reflection", &self.reflection)
             .field("src_path", &self.src_path)
+            .field("defines", &self.defines)
+            .field("imports", &self.imports)
+            .field("generation", &self.generation)
             .field("module", &(&self.module as *const _))
         .finish()
     }
@@ -9,7 +9,8 @@ use std::sync::Arc;
 use math::Transform;
 use gfx::ShaderStage;
 use futures_lite::future::block_on;
-use rend::{CameraDescriptor, GlobalBindGroup, Model};
+use gfx::BindGroupState;
+use rend::{CameraDescriptor, GlobalBindGroup, Model, ModelBindGroup};
 use window::{Action, Key, Window, WindowBackend, WindowEvent};
 
 #[global_allocator]
@@ -84,103 +85,140 @@ impl FlyCamera {
 }
 
 // FIXME: consider combining render textures with textures, or have a trait for anything with a view
-fn create_post_pipeline(context: &mut gfx::RenderContext, target: &gfx::RenderTexture) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
+fn create_post_pipeline(context: &mut gfx::RenderContext, target: &gfx::RenderTexture, constants: &gfx::ShaderConstants) -> (Arc<wgpu::RenderPipeline>, wgpu::BindGroup) {
     let shader = context.create_shader("shaders/post.wgsl");
 
-    let layout = context.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("Post Bind Group Layout"),
-        entries: &[
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 1,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                count: None,
-            },
-        ],
-    });
+    let layout = context.create_shader_bind_group_layout(&shader, 0);
 
+    let entries = [
+        wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(&target.view),
+        },
+        wgpu::BindGroupEntry {
+            binding: 1,
+            resource: wgpu::BindingResource::Sampler(&target.sampler.as_raw()),
+        },
+    ];
+    // Only bind what the shader declares, if it failed to load error.wgsl declares nothing
+    let entries = entries.into_iter()
+        .filter(|entry| shader.reflection().resource(0, entry.binding).is_some())
+    .collect::<Vec<_>>();
     let bind_group = context.device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Post Bind Group"),
         layout: &layout,
-        entries: &[
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(&target.view),
-            },
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: wgpu::BindingResource::Sampler(&target.sampler.as_raw()),
-            },
-        ],
+        entries: &entries,
     });
 
-    let pipeline = context.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Post pipeline"),
-        cache: None,
-        layout: Some!(&context.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            push_constant_ranges: &[],
-            bind_group_layouts: &[&layout],
-        })),
-        vertex: wgpu::VertexState {
-            buffers: &[],
-            module: &shader.as_raw(),
-            entry_point: Some(shader.get_entry(ShaderStage::Vertex).unwrap()),
-            compilation_options: wgpu::PipelineCompilationOptions {
-                ..Default::default()
+    let pipeline = context.create_render_pipeline("Post pipeline", &shader, constants, |context, options| {
+        let pipeline_layout = context.create_pipeline_layout(&shader, &[])?;
+        return Ok(context.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post pipeline"),
+            cache: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                buffers: &[],
+                module: &shader.as_raw(),
+                entry_point: Some(&shader.get_entry(ShaderStage::Vertex).unwrap()),
+                compilation_options: options.clone(),
             },
-        },
-        multisample: wgpu::MultisampleState{
-            count: 1,
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-        multiview: None,
-        fragment: Some(wgpu::FragmentState {
-            module: &shader.as_raw(),
-            entry_point: Some(shader.get_entry(ShaderStage::Fragment).unwrap()),
-            compilation_options: wgpu::PipelineCompilationOptions {
+            multisample: wgpu::MultisampleState{
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            fragment: Some(wgpu::FragmentState {
+                module: &shader.as_raw(),
+                entry_point: Some(&shader.get_entry(ShaderStage::Fragment).unwrap()),
+                compilation_options: options,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: context.config.format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            operation: wgpu::BlendOperation::Add,
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            depth_stencil: None,
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                topology: wgpu::PrimitiveTopology::TriangleList,
                 ..Default::default()
             },
-            targets: &[Some(wgpu::ColorTargetState {
-                format: context.config.format,
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent {
-                        operation: wgpu::BlendOperation::Add,
-                        src_factor: wgpu::BlendFactor::SrcAlpha,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                    },
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::One,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                }),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-        }),
-        depth_stencil: None,
-        primitive: wgpu::PrimitiveState {
-            front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            ..Default::default()
-        },
-    });
+        }));
+    }).expect("The post layout comes from its own shader so always matches");
 
     return (pipeline, bind_group)
 }
 
+fn create_render_pipeline(context: &mut gfx::RenderContext, shader: &gfx::Shader, constants: &gfx::ShaderConstants, groups: &[&wgpu::BindGroupLayoutDescriptor]) -> Result<Arc<wgpu::RenderPipeline>, String> {
+    return context.create_render_pipeline("Render pipeline", shader, constants, |context, options| {
+        let pipeline_layout = context.create_pipeline_layout(shader, groups)?;
+        return Ok(context.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render pipeline"),
+            cache: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader.as_raw(),
+                entry_point: Some(&shader.get_entry(ShaderStage::Vertex).unwrap()),
+                buffers: &[<rend::Vertex as gfx::VertexArrayObject>::VERTEX_BUFFER_LAYOUT],
+                compilation_options: options.clone(),
+            },
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            fragment: Some(wgpu::FragmentState {
+                module: &shader.as_raw(),
+                entry_point: Some(&shader.get_entry(ShaderStage::Fragment).unwrap()),
+                compilation_options: options,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: context.config.format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            operation: wgpu::BlendOperation::Add,
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                bias: Default::default(),
+                depth_write_enabled: true,
+                stencil: Default::default(),
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_compare: wgpu::CompareFunction::Less,
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None!(wgpu::Face::Back),
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+        }));
+    });
+}
+
 // TODO: not this make abstraction
 async fn wgpu_test() {
     let mut window = Window::new("Pregen: Runtime", (800, 800), WindowBackend::from_env());
@@ -188,12 +226,14 @@ async fn wgpu_test() {
     let mut target = context.create_render_target(Some("Test Target"), gfx::SamplerMode::CLAMP, context.config.width, context.config.height);
     dbg!(&target);
     let image = Arc::new(Image::from_raw(include_bytes!("test.jpg").to_vec(), 5));
-    let texture = context.create_texture(Some("Test Texture"), gfx::SamplerMode::REPEAT, gfx::TextureFormat::Rgba8Unorm, image);
+    let texture = image.to_texture(&context.gpu, Some(String::from("Test Texture")), gfx::TextureFormat::Rgba8Unorm.into());
     dbg!(&texture);
     let mut depth_texture = context.create_depth_texture(Some("Depth Texture"), gfx::SamplerMode::CLAMP, context.config.width, context.config.height);
     dbg!(&depth_texture);
-    let mesh = gfx::Geometry::from_primitive(&context.device, Some("Test Mesh"), &gfx::Geometry::PYRAMID);
+    let mesh = gfx::Geometry::from_primitive(&mut context.geometry, Some("Test Mesh"), &gfx::Geometry::PYRAMID);
     dbg!(&mesh);
+    // Never drawn, its space goes straight back to the models loaded below
+    mesh.free(&mut context.geometry);
 
     let shader = context.create_shader("shaders/default.wgsl");
     dbg!(&shader);
@@ -205,13 +245,15 @@ async fn wgpu_test() {
     camera.transform.translation.z = 3.0;
     camera.look_at((0.0, 0.0, 0.0).into());
     let mut camera = FlyCamera::new(camera, 0.02, 0.0015);
-    let remi = Model::from_path(&mut context, Some("Remi"), "models/remi/remi.obj", Transform::default());
-    let sponza = Model::from_path(&mut context, Some("Sponza"), "models/sponza/sponza.obj", Transform::default());
-    let model = Model::from_path(&mut context, Some("Backpack"), "models/backpack/backpack.obj", Transform::default());
+    // Every model shares one bind group, drawn with its own dynamic offsets
+    let mut model_bind = context.create_bind_group::<ModelBindGroup>(None);
+    let remi = Model::from_path(&mut context, &mut model_bind, Some("Remi"), "models/remi/remi.obj", Transform::default());
+    let sponza = Model::from_path(&mut context, &mut model_bind, Some("Sponza"), "models/sponza/sponza.obj", Transform::default());
+    let mut model = Model::from_path(&mut context, &mut model_bind, Some("Backpack"), "models/backpack/backpack.obj", Transform::default());
+    model_bind.refresh(&context.device);
     let mut frame_bind = context.create_bind_group::<GlobalBindGroup>(None);
-    let lights = (0..32).map(|_| {
+    let lights = (0..rend::LIGHT_CAPACITY).map(|_| {
         rend::LightingUniform {
-            _padding: 0.0,
             position: glam::Vec3::new(
                 rand::random::<f32>() * 13.75 - 4.65,
                 rand::random::<f32>() * 13.75 - 4.65,
@@ -219,75 +261,31 @@ async fn wgpu_test() {
             ),
             intensity: rand::random::<f32>() * 1.35,
             color: glam::Vec3::new(rand::random::<f32>(), rand::random::<f32>(), rand::random::<f32>()),
+            ..Default::default()
         }
     }).collect::<Vec<_>>();
-    frame_bind.u_lights.set(lights.try_into().expect("Expected exactly 32 lights"));
-    let pipeline = context.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Render pipeline"),
-        cache: None,
-        layout: Some(&context.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            push_constant_ranges: &[],
-            bind_group_layouts: &[&frame_bind.layout, &camera.camera.group.layout, &model.group.layout, &model.meshes[0].material.group.layout],
-        })),
-        vertex: wgpu::VertexState {
-            module: &shader.as_raw(),
-            entry_point: Some(shader.get_entry(ShaderStage::Vertex).unwrap()),
-            buffers: &[<rend::Vertex as gfx::VertexArrayObject>::VERTEX_BUFFER_LAYOUT],
-            compilation_options: wgpu::PipelineCompilationOptions {
-                ..Default::default()
-            },
-        },
-        multisample: wgpu::MultisampleState {
-            count: 1,
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-        multiview: None,
-        fragment: Some(wgpu::FragmentState {
-            module: &shader.as_raw(),
-            entry_point: Some(shader.get_entry(ShaderStage::Fragment).unwrap()),
-            compilation_options: wgpu::PipelineCompilationOptions {
-                ..Default::default()
-            },
-            targets: &[Some(wgpu::ColorTargetState {
-                format: context.config.format,
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent {
-                        operation: wgpu::BlendOperation::Add,
-                        src_factor: wgpu::BlendFactor::SrcAlpha,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                    },
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::One,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                }),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-        }),
-        depth_stencil: Some(wgpu::DepthStencilState {
-            bias: Default::default(),
-            depth_write_enabled: true,
-            stencil: Default::default(),
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_compare: wgpu::CompareFunction::Less,
-        }),
-        primitive: wgpu::PrimitiveState {
-            front_face: wgpu::FrontFace::Ccw,
-            cull_mode: None!(wgpu::Face::Back),
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            ..Default::default()
-        },
-    });
+    frame_bind.u_lights.set(lights.try_into().expect("Expected exactly LIGHT_CAPACITY lights"));
+    let constants = gfx::ShaderConstants::new().set("light_count", rend::LIGHT_CAPACITY as u32);
+    // Laid out like the bind groups it's drawn with, in the order they're bound
+    let groups = [
+        &frame_bind.get_layout_descriptor(),
+        &camera.camera.group.get_layout_descriptor(),
+        &model_bind.get_layout_descriptor(),
+        &model.meshes[0].material.group.get_layout_descriptor(),
+    ];
+    let mut pipeline = create_render_pipeline(&mut context, &shader, &constants, &groups)
+        .unwrap_or_else(|error| panic!("{}", error));
+    let mut pipeline_generation = shader.generation();
 
     let mut frame: f32 = 0.0;
     dbg!(&model.transform);
     let mut capture_mouse = false;
     dbg!(&camera.camera.transform);
     // camera.update(&mut context, &window);
-    let (mut post_pipeline, mut post_bind_group) = create_post_pipeline(&mut context, &target);
+    let post_shader = context.create_shader("shaders/post.wgsl");
+    let mut post_constants = gfx::ShaderConstants::new().set("threshold", 3.25f32);
+    let (mut post_pipeline, mut post_bind_group) = create_post_pipeline(&mut context, &target, &post_constants);
+    let mut post_generation = post_shader.generation();
     while !window.should_close() {
         profiling::finish_frame!();
 
@@ -321,14 +319,13 @@ async fn wgpu_test() {
                         context.surface.configure(&context.device, &context.config);
                         target.resize(&context.device, context.config.width, context.config.height);
                         depth_texture.resize(&context.device, context.config.width, context.config.height);
-                        (post_pipeline, post_bind_group) = create_post_pipeline(&mut context, &target);
+                        (post_pipeline, post_bind_group) = create_post_pipeline(&mut context, &target, &post_constants);
                     }
                 }
                 WindowEvent::KeyboardInput(Key::Escape, _, Action::Pressed) => {
                     return;
                 }
                 WindowEvent::KeyboardInput(Key::P, _, Action::Pressed) => {
-                    shader.reload(&context.device);
                     match camera.camera.projection {
                         rend::CameraProjection::Perspective => {
                             camera.camera.projection = rend::CameraProjection::Orthographic;
@@ -342,10 +339,16 @@ async fn wgpu_test() {
                     capture_mouse = !capture_mouse;
                     window.capture_cursor(capture_mouse);
                 }
+                // Load the backpack again from disk, into the slots and geometry the old one leaves behind
+                WindowEvent::KeyboardInput(Key::R, _, Action::Pressed) => {
+                    let transform = model.transform;
+                    model.unload(&mut context, &mut model_bind);
+                    model = Model::from_path(&mut context, &mut model_bind, Some("Backpack"), "models/backpack/backpack.obj", transform);
+                    model_bind.refresh(&context.device);
+                }
                 WindowEvent::KeyboardInput(Key::L, _, Action::Pressed) => {
-                    let lights = (0..32).map(|_| {
+                    let lights = (0..rend::LIGHT_CAPACITY).map(|_| {
                         rend::LightingUniform {
-                            _padding: 0.0,
                             position: glam::Vec3::new(
                                 rand::random::<f32>() * 13.75 - 4.65,
                                 rand::random::<f32>() * 13.75 - 4.65,
@@ -353,9 +356,19 @@ async fn wgpu_test() {
                             ),
                             intensity: rand::random::<f32>() * 1.25,
                             color: glam::Vec3::new(rand::random::<f32>(), rand::random::<f32>(), rand::random::<f32>()),
+                            ..Default::default()
                         }
                     }).collect::<Vec<_>>();
-                    frame_bind.u_lights.set(lights.try_into().expect("Expected exactly 32 lights"));
+                    frame_bind.u_lights.set(lights.try_into().expect("Expected exactly LIGHT_CAPACITY lights"));
+                }
+                // Each threshold gets its own pipeline, only the current one is kept around
+                WindowEvent::KeyboardInput(key @ (Key::LeftBracket | Key::RightBracket), _, Action::Pressed) => {
+                    let step = if key == Key::LeftBracket { -0.25 } else { 0.25 };
+                    let threshold = (post_constants.get("threshold").unwrap() + step).max(0.0);
+                    context.remove_render_pipeline("Post pipeline", &post_shader, &post_constants);
+                    post_constants.insert("threshold", threshold);
+                    log::info!("Post threshold {}", threshold);
+                    (post_pipeline, post_bind_group) = create_post_pipeline(&mut context, &target, &post_constants);
                 }
                 WindowEvent::KeyboardInput(Key::Minus, _, Action::Pressed) => {
                     camera.camera.fov -= 1.0;
@@ -367,9 +380,31 @@ async fn wgpu_test() {
             }
         }
 
+        // Rebuild anything using a shader that changed on disk
+        context.reload_changed_shaders();
+        if shader.generation() != pipeline_generation {
+            let groups = [
+                &frame_bind.get_layout_descriptor(),
+                &camera.camera.group.get_layout_descriptor(),
+                &model_bind.get_layout_descriptor(),
+                &model.meshes[0].material.group.get_layout_descriptor(),
+            ];
+            // A shader that no longer fits the bind groups keeps drawing with its last pipeline
+            match create_render_pipeline(&mut context, &shader, &constants, &groups) {
+                Ok(new_pipeline) => pipeline = new_pipeline,
+                Err(error) => log::error!("Keeping the previous pipeline: {}", error),
+            }
+            pipeline_generation = shader.generation();
+        }
+        if post_shader.generation() != post_generation {
+            (post_pipeline, post_bind_group) = create_post_pipeline(&mut context, &target, &post_constants);
+            post_generation = post_shader.generation();
+        }
+
         frame_bind.u_time.set(frame);
-        frame_bind.update(&context.queue);
-        camera.camera.update(&context.queue);
+        frame_bind.update(&mut context.uploads);
+        model_bind.update(&mut context.uploads);
+        camera.camera.update(&mut context.uploads);
         window.rename(format!("Pregen: Runtime - Frame {:.2}", frame));
         // camera.camera.transform.translation.x = frame.cos() * 3.0;
         // camera.camera.transform.translation.z = frame.sin() * 3.0;
@@ -378,6 +413,8 @@ async fn wgpu_test() {
             camera.update(&window);
             window.move_cursor(context.config.width / 2, context.config.height / 2);
         }
+        // Every uniform written above goes to the GPU in one submit
+        context.uploads.flush(&context.queue);
         let swapchain = match context.surface.get_current_texture() {
             Ok(swapchain) => swapchain,
             // In theory this should never happen unless the surface is changed but not reconfigured
@@ -418,25 +455,28 @@ async fn wgpu_test() {
             });
             rpass.set_pipeline(&pipeline);
             rpass.set_bind_group(0, frame_bind.as_raw(), &[]);
-            rpass.set_bind_group(2, model.group.as_raw(), &[]);
             rpass.set_bind_group(1, camera.camera.group.as_raw(), &[]);
+            // All geometry shares the pool's buffers, meshes only differ by their ranges
+            if let Some(vertices) = context.geometry.vertices.slice() {
+                rpass.set_vertex_buffer(0, vertices);
+            }
+            if let Some(indices) = context.geometry.indices.slice() {
+                rpass.set_index_buffer(indices, context.geometry.indices.format);
+            }
+            rpass.set_bind_group(2, model_bind.as_raw(), &model_bind.dynamic_offsets(model.slot));
             for mesh in &model.meshes {
                 rpass.set_bind_group(3, mesh.material.group.as_raw(), &[]);
-                rpass.set_vertex_buffer(0, mesh.geometry.vertex_buffer.slice(..));
-                rpass.set_index_buffer(mesh.geometry.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                rpass.draw_indexed(mesh.geometry.range(), 0, 0..1);
+                rpass.draw_indexed(mesh.geometry.range(), mesh.geometry.base_vertex(), 0..1);
             }
+            rpass.set_bind_group(2, model_bind.as_raw(), &model_bind.dynamic_offsets(remi.slot));
             for mesh in &remi.meshes {
                 rpass.set_bind_group(3, mesh.material.group.as_raw(), &[]);
-                rpass.set_vertex_buffer(0, mesh.geometry.vertex_buffer.slice(..));
-                rpass.set_index_buffer(mesh.geometry.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                rpass.draw_indexed(mesh.geometry.range(), 0, 0..1);
+                rpass.draw_indexed(mesh.geometry.range(), mesh.geometry.base_vertex(), 0..1);
             }
+            rpass.set_bind_group(2, model_bind.as_raw(), &model_bind.dynamic_offsets(sponza.slot));
             for mesh in &sponza.meshes {
                 rpass.set_bind_group(3, mesh.material.group.as_raw(), &[]);
-                rpass.set_vertex_buffer(0, mesh.geometry.vertex_buffer.slice(..));
-                rpass.set_index_buffer(mesh.geometry.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                rpass.draw_indexed(mesh.geometry.range(), 0, 0..1);
+                rpass.draw_indexed(mesh.geometry.range(), mesh.geometry.base_vertex(), 0..1);
             }
         }
         {
@@ -2,4 +2,4 @@ mod plane;
 mod transform;
 
 pub use plane::Plane;
-pub use transform::Transform;
+pub use transform::{Transform, TransformStd140};
@@ -1,16 +1,13 @@
+use gfx_ne::GpuLayout;
 use glam::{Vec3, Quat};
-use bytemuck::{Pod, Zeroable};
 
-#[repr(C)]
 #[derive(Debug)]
-#[derive(Pod, Zeroable)]
+#[derive(GpuLayout)]
 #[derive(Clone, Copy, PartialEq)]
 pub struct Transform {
-    __padding1: f32,
     pub scale: Vec3,
     pub rotation: Quat,
     pub translation: Vec3,
-    __padding2: f32,
 }
 
 impl Transform {
@@ -19,8 +16,6 @@ impl Transform {
             scale: scale,
             rotation: rotation,
             translation: translation,
-            __padding1: f32::default(),
-            __padding2: f32::default(),
         }
     }
 
@@ -60,11 +60,11 @@ impl Camera {
             transform: desc.transform,
             aspect_ratio: desc.aspect_ratio,
         };
-        camera.update(&ctx.queue);
+        camera.update(&mut ctx.uploads);
         return camera;
     }
 
-    pub fn update(&mut self, queue: &wgpu::Queue) {
+    pub fn update(&mut self, uploads: &mut gfx_ne::StagingBelt) {
         let view = Mat4::from_quat(self.transform.rotation.inverse()) * Mat4::from_translation(-self.transform.translation);
         // FIXME: dont recalculate the projection matrix every frame, only when the transform changes
         let uniform = match self.projection {
@@ -73,7 +73,7 @@ impl Camera {
         };
         self.group.u_camera.set(uniform);
         self.group.u_frustum.set(FrustumUniform::new(self.z_near, self.z_far, self.fov, self.aspect_ratio));
-        self.group.update(&queue);
+        self.group.update(uploads);
     }
 
     fn update_perspective(&mut self, view: Mat4) -> CameraUniform {
@@ -1,19 +1,13 @@
+use gfx_ne::BindGroup;
 use crate::gfx::UniformBuffer;
-use crate::impl_bind_group_state;
 use crate::rend::uniforms::{CameraUniform, FrustumUniform};
 
 #[derive(Debug)]
+#[derive(BindGroup)]
+#[bind_group(label = "Camera Bind Group")]
 pub struct CameraBindGroup {
+    #[uniform(0, label = "Camera Uniform")]
     pub u_camera: UniformBuffer<CameraUniform>,
+    #[uniform(1, label = "Camera Frustum")]
     pub u_frustum: UniformBuffer<FrustumUniform>,
 }
-
-impl_bind_group_state!(
-    "Camera Bind Group",
-    CameraBindGroup,
-
-    "Camera Uniform",
-    u_camera: CameraUniform, 0,
-    "Camera Frustum",
-    u_frustum: FrustumUniform, 1,
-);
@@ -1,37 +1,28 @@
 use glam::Vec2;
+use gfx_ne::BindGroup;
 use crate::gfx::UniformBuffer;
-use crate::impl_bind_group_state;
-use crate::rend::{KeyboardUniform, MouseUniform, CoordinatesUniform, LightingUniform};
+use crate::rend::{KeyboardUniform, MouseUniformStd140, CoordinatesUniformStd140, LightingUniform};
 
+/// Length of `u_lights`, has to match `LIGHT_CAPACITY` in default.wgsl
+pub const LIGHT_CAPACITY: usize = 32;
+
+#[derive(BindGroup)]
+#[bind_group(label = "Global Frame Bind Group")]
 pub struct GlobalBindGroup {
+    #[uniform(0, label = "Game Time")]
     pub u_time: UniformBuffer<f32>,
+    #[uniform(1, label = "Frame Count")]
     pub u_frame: UniformBuffer<u32>,
+    #[uniform(2, label = "Random Seed")]
     pub u_rand_seed: UniformBuffer<u32>,
+    #[uniform(3, label = "Window Resolution")]
     pub u_resolution: UniformBuffer<Vec2>,
-    pub u_mouse: UniformBuffer<MouseUniform>,
+    #[uniform(4, label = "Mouse State")]
+    pub u_mouse: UniformBuffer<MouseUniformStd140>,
+    #[uniform(5, label = "Keyboard State")]
     pub u_keyboard: UniformBuffer<KeyboardUniform>,
-    pub u_lights: UniformBuffer<[LightingUniform; 32]>,
-    pub u_coordinates: UniformBuffer<CoordinatesUniform>,
+    #[uniform(6, label = "Coordinate System")]
+    pub u_coordinates: UniformBuffer<CoordinatesUniformStd140>,
+    #[uniform(7, label = "Lighting")]
+    pub u_lights: UniformBuffer<[LightingUniform; LIGHT_CAPACITY]>,
 }
-
-impl_bind_group_state!(
-    "Global Frame Bind Group",
-    GlobalBindGroup,
-
-    "Game Time",
-    u_time: f32, 0,
-    "Frame Count",
-    u_frame: u32, 1,
-    "Random Seed",
-    u_rand_seed: u32, 2,
-    "Window Resolution",
-    u_resolution: Vec2, 3,
-    "Mouse State",
-    u_mouse: MouseUniform, 4,
-    "Keyboard State",
-    u_keyboard: KeyboardUniform, 5,
-    "Coordinate System",
-    u_coordinates: CoordinatesUniform, 6,
-    "Lighting",
-    u_lights: [LightingUniform; 32], 7
-);
@@ -1,13 +1,21 @@
 use std::sync::Arc;
-use crate::gfx::BindGroupState;
+use gfx_ne::BindGroup;
 use crate::gfx::{Sampler, Texture};
 
+#[derive(BindGroup)]
+#[bind_group(label = "MaterialBindGroup")]
 pub struct MaterialBindGroup {
+    #[texture(0, visibility(fragment))]
     pub albedo: Arc<Texture>,
+    #[sampler(1, visibility(fragment))]
     pub albedo_sampler: Arc<Sampler>,
+    #[texture(2, visibility(fragment))]
     pub normal: Arc<Texture>,
+    #[sampler(3, visibility(fragment))]
     pub normal_sampler: Arc<Sampler>,
+    #[texture(4, visibility(fragment))]
     pub ambient: Arc<Texture>,
+    #[sampler(5, visibility(fragment))]
     pub ambient_sampler: Arc<Sampler>,
 }
 
@@ -23,104 +31,3 @@ impl MaterialBindGroup {
         }
     }
 }
-
-impl BindGroupState for MaterialBindGroup {
-    fn init(_device: &wgpu::Device) -> Self {
-        panic!("MaterialBindGroup requires a pre init state!");
-    }
-
-    fn get_name(&self) -> &'static str {
-        return "Material Bind Group";
-    }
-
-    fn create_bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup {
-        return device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&self.albedo.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&self.albedo_sampler.sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&self.normal.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::Sampler(&self.normal_sampler.sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::TextureView(&self.ambient.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 5,
-                    resource: wgpu::BindingResource::Sampler(&self.ambient_sampler.sampler),
-                },
-            ],
-            label: Some("MaterialBindGroup"),
-        });
-    }
-
-    fn get_layout_descriptor(&self) -> wgpu::BindGroupLayoutDescriptor<'_> {
-        return wgpu::BindGroupLayoutDescriptor {
-            label: Some("MaterialBindGroup"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 5,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ]
-        };
-    }
-
-    fn update(&mut self, _queue: &wgpu::Queue) {}
-}
@@ -1,19 +1,14 @@
 use glam::Mat4;
-use crate::math::Transform;
-use crate::gfx::UniformBuffer;
-use crate::impl_bind_group_state;
+use gfx_ne::BindGroup;
+use crate::math::TransformStd140;
+use crate::gfx::UniformArena;
 
+/// Shared by every model, each one owns a slot in both arenas
+#[derive(BindGroup)]
+#[bind_group(label = "Model Bind Group")]
 pub struct ModelBindGroup {
-    pub u_model: UniformBuffer<Mat4>,
-    pub u_transform: UniformBuffer<Transform>
+    #[uniform(0, dynamic, label = "Model Matrix")]
+    pub u_model: UniformArena<Mat4>,
+    #[uniform(1, dynamic, label = "Transform")]
+    pub u_transform: UniformArena<TransformStd140>
 }
-
-impl_bind_group_state!(
-    "Model Bind Group",
-    ModelBindGroup,
-
-    "Model Matrix",
-    u_model: Mat4, 0,
-    "Transform",
-    u_transform: Transform, 1,
-);
@@ -4,6 +4,6 @@ mod g_camera;
 mod g_material;
 
 pub use g_model::ModelBindGroup;
-pub use g_global::GlobalBindGroup;
+pub use g_global::{GlobalBindGroup, LIGHT_CAPACITY};
 pub use g_camera::CameraBindGroup;
 pub use g_material::MaterialBindGroup;
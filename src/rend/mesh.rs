@@ -9,8 +9,8 @@ pub struct Mesh {
 }
 
 impl Mesh {
-    pub fn new(ctx: &RenderContext, name: Option<&str>, indices: Vec<u32>, vertices: Vec<Vertex>, material: Arc<Material>) -> Self {
-        let geometry = Geometry::new(&ctx.device, name, vertices, indices);
+    pub fn new(ctx: &mut RenderContext, name: Option<&str>, indices: Vec<u32>, vertices: Vec<Vertex>, material: Arc<Material>) -> Self {
+        let geometry = Geometry::new(&mut ctx.geometry, name, vertices, indices);
         return Mesh::from_geometry(name, geometry, material);
     }
 
@@ -22,4 +22,9 @@ impl Mesh {
             material: material
         }
     }
+
+    /// Give the mesh's geometry back to the pool it came from
+    pub fn unload(self, ctx: &mut RenderContext) {
+        self.geometry.free(&mut ctx.geometry);
+    }
 }
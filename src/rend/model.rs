@@ -1,4 +1,5 @@
 use glam::Mat4;
+use gfx_ne::GpuLayout;
 use std::sync::Arc;
 use crate::math::Transform;
 use super::{Material, ModelBindGroup};
@@ -10,32 +11,42 @@ pub struct Model {
     pub name: String,
     pub meshes: Vec<Mesh>,
     pub transform: Transform,
-    pub group: BindGroup<ModelBindGroup>,
+    /// Where the model's uniforms live in the shared `ModelBindGroup`
+    pub slot: usize,
 }
 
 impl Model {
-    pub fn new(ctx: &mut RenderContext, name: Option<&str>, meshes: Vec<Mesh>, transform: Transform) -> Self {
-        let mut group = ctx.create_bind_group::<ModelBindGroup>(None);
-        group.state.u_transform.set(transform);
+    pub fn new(group: &mut BindGroup<ModelBindGroup>, name: Option<&str>, meshes: Vec<Mesh>, transform: Transform) -> Self {
+        let slot = group.u_model.insert(Mat4::IDENTITY);
+        assert_eq!(slot, group.u_transform.insert(transform.std140()), "Model arenas are out of sync");
 
         let mut model = Self {
             name: name.unwrap_or("Unnamed Model").to_string(),
             transform: transform,
             meshes: meshes,
-            group: group,
+            slot: slot,
         };
-        model.update(ctx);
+        model.update(group);
 
         return model;
     }
 
-    pub fn update(&mut self, ctx: &RenderContext) {
+    /// Write the transform into the model's slot, it's uploaded with the rest of `group`
+    pub fn update(&mut self, group: &mut BindGroup<ModelBindGroup>) {
         let mut model_matrix = Mat4::from_translation(self.transform.translation);
         model_matrix = model_matrix * Mat4::from_quat(self.transform.rotation);
         model_matrix = model_matrix * Mat4::from_scale(self.transform.scale);
-        self.group.u_transform.set(self.transform);
-        self.group.u_model.set(model_matrix);
-        self.group.update(&ctx.queue);
+        group.u_transform.set(self.slot, self.transform.std140());
+        group.u_model.set(self.slot, model_matrix);
+    }
+
+    /// Give the model's slots back to `group` and its meshes' geometry back to the pool
+    pub fn unload(self, ctx: &mut RenderContext, group: &mut BindGroup<ModelBindGroup>) {
+        group.u_model.remove(self.slot);
+        group.u_transform.remove(self.slot);
+        for mesh in self.meshes {
+            mesh.unload(ctx);
+        }
     }
 
     fn load_texture_or_empty(base_path: &std::path::Path, tex_name: &Option<String>) -> Arc<Image> {
@@ -50,7 +61,7 @@ impl Model {
         Arc::new(Image::empty())
     }
 
-    pub fn from_path(ctx: &mut RenderContext, name: Option<&str>, path: &str, transform: Transform) -> Self {
+    pub fn from_path(ctx: &mut RenderContext, group: &mut BindGroup<ModelBindGroup>, name: Option<&str>, path: &str, transform: Transform) -> Self {
         let (models, materials) = tobj::load_obj(path, &tobj::LoadOptions {
             triangulate: true,
             single_index: true,
@@ -89,6 +100,6 @@ impl Model {
             let id = mesh.material_id.unwrap_or(0);
             geo.push(Mesh::new(ctx, name, indices, vertices, materials[id].clone()));
         }
-        return Self::new(ctx, name, geo, transform);
+        return Self::new(group, name, geo, transform);
     }
 }
@@ -5,9 +5,14 @@ mod u_lighting;
 mod u_keyboard;
 mod u_coordinates;
 
-pub use u_mouse::MouseUniform;
+/// Structs and binding constants generated from the shaders by `build.rs`
+pub mod shaders {
+    include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+}
+
+pub use u_mouse::MouseUniformStd140;
 pub use u_camera::CameraUniform;
 pub use u_frustrum::FrustumUniform;
 pub use u_lighting::LightingUniform;
 pub use u_keyboard::KeyboardUniform;
-pub use u_coordinates::CoordinatesUniform;
+pub use u_coordinates::CoordinatesUniformStd140;
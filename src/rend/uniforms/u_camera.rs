@@ -1,28 +1,20 @@
-use glam::{Mat4, Vec4, Vec3};
-use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+use bytemuck::Zeroable;
 
 use crate::math::Transform;
 
-#[repr(C)]
-#[derive(Debug)]
-#[derive(Pod, Zeroable)]
-#[derive(Clone, Copy, PartialEq)]
-pub struct CameraUniform { // Vec4::w is only used for padding, probably a bad idea
-    pub view: Mat4,
-    pub position: Vec4,
-    pub direction: Vec4,
-    pub projection: Mat4,
-    pub view_projection: Mat4,
-}
+/// Generated from `Camera` in default.wgsl
+pub type CameraUniform = super::shaders::default::Camera;
 
 impl Default for CameraUniform {
     fn default() -> Self {
         Self {
             view: Mat4::IDENTITY,
-            position: Vec4::ZERO,
-            direction: Vec4::ZERO,
+            position: Vec3::ZERO,
+            direction: Vec3::ZERO,
             projection: Mat4::IDENTITY,
             view_projection: Mat4::IDENTITY,
+            ..Zeroable::zeroed()
         }
     }
 }
@@ -33,8 +25,9 @@ impl CameraUniform {
             view: view,
             projection: proj,
             view_projection: proj * view,
-            position: position.extend(0.0),
-            direction: direction.extend(0.0),
+            position: position,
+            direction: direction,
+            ..Zeroable::zeroed()
         }
     }
 
@@ -1,10 +1,10 @@
 use glam::Vec3;
-use bytemuck::{Pod, Zeroable};
+use gfx_ne::GpuLayout;
 
-#[repr(C)]
 #[derive(Debug)]
-#[derive(Pod, Zeroable)]
+#[derive(GpuLayout)]
 #[derive(Clone, Copy, PartialEq)]
+#[gpu_layout(wgsl = "Coordinates")]
 pub struct CoordinatesUniform {
     /// The world space forward vector
     pub forward: Vec3,
@@ -23,3 +23,9 @@ impl Default for CoordinatesUniform {
         }
     }
 }
+
+impl Default for CoordinatesUniformStd140 {
+    fn default() -> Self {
+        return CoordinatesUniform::default().std140();
+    }
+}
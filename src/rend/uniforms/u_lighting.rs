@@ -1,16 +1,8 @@
 use glam::Vec3;
-use bytemuck::{Pod, Zeroable};
+use bytemuck::Zeroable;
 
-#[repr(C)]
-#[derive(Debug)]
-#[derive(Pod, Zeroable)]
-#[derive(Clone, Copy, PartialEq)]
-pub struct LightingUniform {
-    pub color: Vec3,
-    pub _padding: f32,
-    pub position: Vec3,
-    pub intensity: f32,
-}
+/// Generated from `LightingUniform` in default.wgsl
+pub type LightingUniform = super::shaders::default::LightingUniform;
 
 impl Default for LightingUniform {
     fn default() -> Self {
@@ -18,7 +10,7 @@ impl Default for LightingUniform {
             color: Vec3::ZERO,
             position: Vec3::ZERO,
             intensity: 0.0,
-            _padding: 0.0,
+            ..Zeroable::zeroed()
         }
     }
 }
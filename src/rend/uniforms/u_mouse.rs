@@ -1,10 +1,10 @@
+use gfx_ne::GpuLayout;
 use glam::{Vec2, Vec3};
-use bytemuck::{Pod, Zeroable};
 
-#[repr(C)]
 #[derive(Debug)]
-#[derive(Pod, Zeroable)]
+#[derive(GpuLayout)]
 #[derive(Clone, Copy, PartialEq)]
+#[gpu_layout(wgsl = "Mouse")]
 pub struct MouseUniform {
     pub state: Vec3, // X = left, right = Y, middle = Z
     pub position: Vec2,
@@ -18,3 +18,9 @@ impl Default for MouseUniform {
         }
     }
 }
+
+impl Default for MouseUniformStd140 {
+    fn default() -> Self {
+        return MouseUniform::default().std140();
+    }
+}
@@ -1,11 +1,10 @@
 use glam::{Vec2, Vec3};
+use gfx_ne::VertexLayout;
 use bytemuck::{Pod, Zeroable};
-use crate::gfx::VertexArrayObject;
-use std::mem::{offset_of, size_of};
-
 
 #[repr(C)]
 #[derive(Debug)]
+#[derive(VertexLayout)]
 #[derive(Pod, Zeroable)]
 #[derive(Copy, Clone, PartialEq)]
 pub struct Vertex {
@@ -43,32 +42,3 @@ impl Vertex {
         return Self::create_triangle(points, normal, uv);
     }
 }
-
-impl VertexArrayObject for Vertex {
-    const VERTEX_BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
-        step_mode: wgpu::VertexStepMode::Vertex,
-        array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
-        attributes: &[
-            wgpu::VertexAttribute {
-                shader_location: 0,
-                format: wgpu::VertexFormat::Float32x3,
-                offset: offset_of!(Vertex, position) as wgpu::BufferAddress,
-            },
-            wgpu::VertexAttribute {
-                shader_location: 1,
-                format: wgpu::VertexFormat::Float32x3,
-                offset: offset_of!(Vertex, normal) as wgpu::BufferAddress,
-            },
-            wgpu::VertexAttribute {
-                shader_location: 2,
-                format: wgpu::VertexFormat::Float32x3,
-                offset: offset_of!(Vertex, color) as wgpu::BufferAddress,
-            },
-            wgpu::VertexAttribute {
-                shader_location: 3,
-                format: wgpu::VertexFormat::Float32x2,
-                offset: offset_of!(Vertex, uv) as wgpu::BufferAddress,
-            }
-        ]
-    };
-}
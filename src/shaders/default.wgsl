@@ -1,3 +1,13 @@
+// Lighting permutations are selected with defines, e.g. `MAX_LIGHTS=8` to only
+// light with the first 8 lights, or one of `DEBUG_NORMAL`, `DEBUG_UV` and
+// `DEBUG_POSITION` for debug views
+#ifndef MAX_LIGHTS
+#define MAX_LIGHTS 32
+#endif
+
+// Size of `GlobalBindGroup::u_lights`, fixed so no permutation changes the layout
+const LIGHT_CAPACITY: u32 = 32u;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
@@ -31,7 +41,9 @@ struct LightingUniform {
 var<uniform> uTime: f32;
 
 @group(0) @binding(7)
-var<uniform> uLights: array<LightingUniform, 32>;
+var<uniform> uLights: array<LightingUniform, LIGHT_CAPACITY>;
+// How many of uLights are actually lit, set when the pipeline is created
+override light_count: u32 = LIGHT_CAPACITY;
 
 @group(1) @binding(0)
 var<uniform> uCamera: Camera;
@@ -46,12 +58,21 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 
     let world_normal = normalize(mat3x3<f32>(uModel[0].xyz, uModel[1].xyz, uModel[2].xyz) * in.normal);
 
+    // The normal and position debug views show the mesh's own data, before the model matrix
     return VertexOutput(
         clip_position,
+#if defined(DEBUG_NORMAL)
+        in.normal,
+#else
         world_normal,
+#endif
         in.color,
         in.uv,
+#if defined(DEBUG_POSITION)
+        in.position
+#else
         world_position.xyz
+#endif
     );
 }
 
@@ -111,6 +132,13 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let tbn = compute_tbn(in.world_position, geo_normal, uv);
     let world_normal = normalize(tbn * normal_sample);
 
+#if defined(DEBUG_NORMAL)
+    return vec4<f32>(in.normal * 0.5 + 0.5, 1.0);
+#elif defined(DEBUG_UV)
+    return vec4<f32>(in.uv, 0.0, 1.0);
+#elif defined(DEBUG_POSITION)
+    return vec4<f32>(in.world_position, 1.0);
+#else
     let view_dir = normalize(uCamera.position - in.world_position);
 
     // Small constant so unlit areas aren't pure black, modulated by the
@@ -120,7 +148,8 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let shininess = 27.25;
     let specular_strength = 0.5;
 
-    for (var i: u32 = 0; i < 32; i = i + 1) {
+    let lit = min(min(light_count, MAX_LIGHTS), LIGHT_CAPACITY);
+    for (var i: u32 = 0; i < lit; i = i + 1) {
         let light = uLights[i];
         let to_light = light.position - in.world_position;
         let distance = length(to_light) / 1.65;
@@ -143,4 +172,5 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let final_color = aces_tonemap(color);
     return vec4<f32>(final_color, albedo.a);
+#endif
 }
@@ -18,8 +18,8 @@ fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput{
 var texture: texture_2d<f32>;
 @group(0) @binding(1)
 var texture_sampler: sampler;
-// @group(0) @binding(2)
-// var<uniform> threshold: f32;
+// Edges weaker than this are left untouched, set when the pipeline is created
+override threshold: f32 = 3.25;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
@@ -55,7 +55,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let edge = sqrt(sum_x * sum_x + sum_y * sum_y);
 
-    if (edge < 3.25) {
+    if (edge < threshold) {
         return textureSample(texture, texture_sampler, in.uv);
     }
 
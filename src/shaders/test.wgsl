@@ -1,4 +1,4 @@
-#import "types/camera.wgsl"
+#import pregen::camera
 
 struct VertexOutput {
 	@location(0) uv: vec2<f32>,